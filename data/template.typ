@@ -1,3 +1,5 @@
+#set page(..page-setup)
+
 #set table(
   stroke: (x, y) => if y == 0 {
     (bottom: 0.7pt + black)
@@ -21,6 +23,11 @@
   #preamble
 ]
 
+#if toc {
+  pagebreak()
+  outline(title: [Contents], depth: 1)
+}
+
 // Page heading
 #set page(
   numbering: "1",
@@ -66,6 +73,11 @@
   }
 
   #for q in problems {
+    if "body" in q {
+      pagebreak()
+      q.body
+      continue
+    }
     [
       #pagebreak()
       = #q.title
@@ -0,0 +1,99 @@
+use crate::{
+    language::LanguageSet, packet::Packet, Accounts, Config, RawOrImport, RenderConfig, Setup,
+    TestRunner,
+};
+
+/// Builder for constructing a [`Config`] in code
+///
+/// ```
+/// # use bedrock::{Config, packet::Packet};
+/// let config = Config::builder()
+///     .port(8080)
+///     .packet(Packet {
+///         title: "My Packet".into(),
+///         ..Default::default()
+///     })
+///     .build();
+/// assert_eq!(8080, config.port);
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct ConfigBuilder {
+    config: Config,
+}
+
+impl ConfigBuilder {
+    /// Create a builder for the default config, see [`Config::builder`]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set how the container is set up, see [`Config::setup`]
+    pub fn setup(mut self, setup: impl Into<RawOrImport<Setup>>) -> Self {
+        self.config.setup = Some(setup.into());
+        self
+    }
+
+    /// Set the port on which the server is hosted, see [`Config::port`]
+    pub fn port(mut self, port: u16) -> Self {
+        self.config.port = port;
+        self
+    }
+
+    /// Set the languages in which solutions may be submitted, see [`Config::languages`]
+    pub fn languages(mut self, languages: impl Into<RawOrImport<LanguageSet>>) -> Self {
+        self.config.languages = languages.into();
+        self
+    }
+
+    /// Set the admin and competitor accounts, see [`Config::accounts`]
+    pub fn accounts(mut self, accounts: impl Into<RawOrImport<Accounts>>) -> Self {
+        self.config.accounts = accounts.into();
+        self
+    }
+
+    /// Set the packet for the competition, see [`Config::packet`]
+    pub fn packet(mut self, packet: impl Into<RawOrImport<Packet>>) -> Self {
+        self.config.packet = packet.into();
+        self
+    }
+
+    /// Set the configuration for the test runner, see [`Config::test_runner`]
+    pub fn test_runner(mut self, test_runner: impl Into<RawOrImport<TestRunner>>) -> Self {
+        self.config.test_runner = test_runner.into();
+        self
+    }
+
+    /// Set the configuration for rendering the packet, see [`Config::render`]
+    pub fn render(mut self, render: impl Into<RawOrImport<RenderConfig>>) -> Self {
+        self.config.render = render.into();
+        self
+    }
+
+    /// Set when the competition starts, see [`Config::start_time`]
+    pub fn start_time(mut self, start_time: time::OffsetDateTime) -> Self {
+        self.config.start_time = Some(start_time);
+        self
+    }
+
+    /// Set when the competition ends, see [`Config::end_time`]
+    pub fn end_time(mut self, end_time: time::OffsetDateTime) -> Self {
+        self.config.end_time = Some(end_time);
+        self
+    }
+
+    /// Build the config
+    ///
+    /// As with a parsed config, [`Config::hash`] only depends on the contents, so building the
+    /// same contents always gives the same hash.
+    pub fn build(mut self) -> Config {
+        self.config.hash = self.config.canonical_hash_u64();
+        self.config
+    }
+}
+
+impl Config {
+    /// Create a [`ConfigBuilder`] with default values
+    pub fn builder() -> ConfigBuilder {
+        ConfigBuilder::new()
+    }
+}
@@ -0,0 +1,67 @@
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    language::{Language, LanguageSet},
+    packet::Packet,
+    Config,
+};
+
+/// The parts of a [`Config`] that are safe to send to competitors
+///
+/// This does not contain any admin accounts, passwords, hidden tests, reference solutions,
+/// checkers, or setup (or language `init`) commands.  See
+/// [`Config::competitor_view`].
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+#[serde(deny_unknown_fields)]
+pub struct CompetitorConfig {
+    /// Port on which the server is hosted
+    pub port: u16,
+    /// Languages which may be used to solve problems
+    pub languages: LanguageSet,
+    /// Names of the competitors participating in the competition
+    pub competitors: Vec<String>,
+    /// The packet, with only the visible tests for each problem
+    pub packet: Packet,
+}
+
+impl Config {
+    /// Strip everything from this config that competitors must not see
+    pub fn competitor_view(&self) -> CompetitorConfig {
+        let mut packet = (*self.packet).clone();
+        for problem in packet.problems.iter_mut() {
+            problem.tests.retain(|t| t.visible);
+            // paths on the server are of no use to competitors
+            problem.template = None;
+            problem.solution = None;
+            problem.solution_language = None;
+            problem.checker = None;
+            problem.checker_language = None;
+            for test in problem.tests.iter_mut() {
+                test.input_file = None;
+                test.output_file = None;
+            }
+        }
+
+        let mut languages = LanguageSet::with_capacity(self.languages.len());
+        for language in self.languages.iter() {
+            let mut language = language.clone();
+            // setting up the host is of no concern to competitors
+            if let Language::Custom { init, .. } = &mut language {
+                *init = None;
+            }
+            languages.insert(language);
+        }
+
+        CompetitorConfig {
+            port: self.port,
+            languages,
+            competitors: self
+                .accounts
+                .competitors
+                .iter()
+                .map(|c| c.name.clone())
+                .collect(),
+            packet,
+        }
+    }
+}
@@ -6,10 +6,14 @@ pub mod duration {
     where
         S: Serializer,
     {
-        // NOTE: this can technically serialise a duration >= 2^64, while the deserialiser can only
-        // deserialise up to 2^64 - 1, but I'd be quite concerned if we have a duration that is longer
-        // than 585 million years
-        value.as_millis().serialize(ser)
+        // NOTE: durations >= 2^64 ms are saturated, since most formats (e.g., TOML) can't represent
+        // a u128, but I'd be quite concerned if we have a duration that is longer than 585 million
+        // years
+        millis(value).serialize(ser)
+    }
+
+    pub(crate) fn millis(value: &Duration) -> u64 {
+        u64::try_from(value.as_millis()).unwrap_or(u64::MAX)
     }
 
     pub fn deserialize<'de, D>(de: D) -> Result<Duration, D::Error>
@@ -19,3 +23,45 @@ pub mod duration {
         Ok(Duration::from_millis(u64::deserialize(de)?))
     }
 }
+
+pub mod command_duration {
+    use serde::{Deserialize, Deserializer, Serialize, Serializer};
+    use std::time::Duration;
+
+    use crate::CommandConfig;
+
+    pub fn serialize<S>(value: &CommandConfig<Duration>, ser: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        value.as_ref().map(super::duration::millis).serialize(ser)
+    }
+
+    pub fn deserialize<'de, D>(de: D) -> Result<CommandConfig<Duration>, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        Ok(CommandConfig::<u64>::deserialize(de)?.map(Duration::from_millis))
+    }
+}
+
+pub mod script {
+    use serde::{Deserialize, Deserializer};
+
+    use crate::{roi, RawOrImport};
+
+    /// A script, which may also be given as a list of commands that are joined with newlines
+    pub fn deserialize<'de, D>(de: D) -> Result<Option<RawOrImport<String, roi::Raw>>, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        // see `RawOrImport::deserialize`
+        let content = serde::__private::de::Content::deserialize(de)?;
+        let de = serde::__private::de::ContentRefDeserializer::<D::Error>::new(&content);
+
+        if let Ok(lines) = Vec::<String>::deserialize(de) {
+            return Ok(Some(lines.join("\n").into()));
+        }
+        Option::deserialize(de)
+    }
+}
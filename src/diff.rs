@@ -0,0 +1,97 @@
+use std::collections::BTreeMap;
+
+use xxhash_rust::xxh3;
+
+use crate::{packet::Problem, util, Config};
+
+/// What changed between two versions of a [`Config`], see [`Config::diff`]
+///
+/// Problems are matched by title, so renaming a problem shows up as one problem being removed
+/// and another being added.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct ConfigDiff {
+    /// Titles of the problems which are only in the new config
+    pub added_problems: Vec<String>,
+    /// Titles of the problems which are only in the old config
+    pub removed_problems: Vec<String>,
+    /// Titles of the problems which are in both configs, but with different contents
+    pub modified_problems: Vec<String>,
+    /// Whether any language was added, removed, or changed
+    pub languages_changed: bool,
+    /// Whether any admin or competitor account was added, removed, or changed
+    pub accounts_changed: bool,
+    /// Whether the server's port changed
+    pub port_changed: bool,
+}
+
+impl ConfigDiff {
+    /// Whether nothing that this diff tracks has changed
+    pub fn is_empty(&self) -> bool {
+        *self == Self::default()
+    }
+}
+
+/// Hash of everything in a problem, so that modified problems can be found
+fn problem_hash(problem: &Problem) -> u64 {
+    let problem = serde_json::to_vec(problem).expect("problems can always be serialised to JSON");
+    xxh3::xxh3_64(&problem)
+}
+
+impl Config {
+    /// Find what changed from this config to `other`, e.g. so that a server only has to do
+    /// what is needed when its config is reloaded
+    pub fn diff(&self, other: &Config) -> ConfigDiff {
+        let hashes = |config: &Config| {
+            config
+                .packet
+                .iter_problems()
+                .map(|p| (p.title.clone(), problem_hash(p)))
+                .collect::<BTreeMap<_, _>>()
+        };
+        let (old, new) = (hashes(self), hashes(other));
+
+        let mut diff = ConfigDiff {
+            languages_changed: self.languages != other.languages,
+            accounts_changed: self.accounts != other.accounts,
+            port_changed: self.port != other.port,
+            ..Default::default()
+        };
+        for (title, hash) in &new {
+            match old.get(title) {
+                None => diff.added_problems.push(title.clone()),
+                Some(old_hash) if old_hash != hash => diff.modified_problems.push(title.clone()),
+                Some(_) => {}
+            }
+        }
+        diff.removed_problems = old
+            .into_keys()
+            .filter(|title| !new.contains_key(title))
+            .collect();
+        diff
+    }
+
+    /// A fingerprint of each problem in the packet, in order, as pairs of the problem's title and
+    /// a hash of its contents
+    ///
+    /// Only the title, the description (as written, before rendering), the tests, and the
+    /// allowed languages are included, so a fingerprint changes exactly when what competitors
+    /// are shown or judged against changes.
+    pub fn problem_fingerprints(&self) -> Vec<(String, String)> {
+        self.packet
+            .iter_problems()
+            .map(|p| (p.title.clone(), util::encode_hash(problem_fingerprint(p))))
+            .collect()
+    }
+}
+
+/// Hash of the parts of a problem used by [`Config::problem_fingerprints`]
+fn problem_fingerprint(problem: &Problem) -> u64 {
+    let value = serde_json::json!({
+        "title": problem.title,
+        "description": problem.description,
+        "tests": problem.tests,
+        "languages": problem.languages,
+    });
+    let bytes = serde_json::to_vec(&value).expect("problems can always be serialised to JSON");
+    xxh3::xxh3_64(&bytes)
+}
@@ -0,0 +1,277 @@
+use std::{
+    collections::BTreeMap,
+    path::{Path, PathBuf},
+};
+
+use miette::Diagnostic;
+
+use crate::{validate::escapes_directory, Config, FileCopy};
+
+#[derive(Debug, thiserror::Error, Diagnostic)]
+pub enum FileCopyError {
+    /// `from` or one of the `exclude` patterns is not a valid glob pattern
+    #[error("Invalid glob pattern '{pattern}': {source}")]
+    Pattern {
+        pattern: String,
+        #[source]
+        source: glob::PatternError,
+    },
+    /// `from` is a glob pattern that does not match any files
+    #[error("'{0}' did not match any files")]
+    NoMatches(String),
+    /// `from` is a directory, but `to` is an existing file
+    #[error(
+        "Cannot copy directory '{}' into '{}', which is not a directory",
+        from.display(),
+        to.display()
+    )]
+    DirectoryIntoFile { from: PathBuf, to: PathBuf },
+    /// `from` is a directory which is larger than [`FileCopy::MAX_DIRECTORY_SIZE`]
+    #[error("Directory '{}' is larger than {} bytes", .0.display(), FileCopy::MAX_DIRECTORY_SIZE)]
+    DirectoryTooLarge(PathBuf),
+    /// `from` is a glob pattern which matches more than one file with the same name, which would
+    /// be copied to the same place
+    #[error(
+        "'{}' and '{}' would both be copied to '{}'",
+        first.display(),
+        second.display(),
+        to.display()
+    )]
+    DuplicateDestination {
+        first: PathBuf,
+        second: PathBuf,
+        to: PathBuf,
+    },
+    /// `to` is absolute or escapes the test directory
+    #[error(
+        "Cannot copy to '{}', which is not a relative path within the test directory",
+        .0.display()
+    )]
+    UnsafeDestination(PathBuf),
+    /// The files were unable to be copied due to an IO error
+    #[error("Failed to copy file: {0}")]
+    Io(#[from] std::io::Error),
+}
+
+impl FileCopy {
+    /// The largest total size, in bytes, of a directory that will be copied
+    pub const MAX_DIRECTORY_SIZE: u64 = 256 * 1024 * 1024;
+
+    /// Whether [`FileCopy::from`] is a glob pattern rather than a single path
+    pub fn is_glob(&self) -> bool {
+        self.from.to_string_lossy().contains(['*', '?', '['])
+    }
+
+    /// Whether [`FileCopy::from`] is a directory, so the whole tree will be copied
+    pub fn is_dir(&self, config: &Config) -> bool {
+        !self.is_glob() && config.resolve_path(&self.from).is_dir()
+    }
+
+    /// This copy with [`FileCopy::from`] relative to the directory containing the config, see
+    /// [`Config::resolve_path`]
+    fn resolve(&self, config: &Config) -> FileCopy {
+        let from = if self.is_glob() {
+            // the config's directory may itself contain glob characters
+            let dir = glob::Pattern::escape(&config.dir.to_string_lossy());
+            Path::new(&dir).join(&self.from)
+        } else {
+            config.resolve_path(&self.from)
+        };
+        FileCopy {
+            from,
+            ..self.clone()
+        }
+    }
+
+    /// Parse the patterns in [`FileCopy::exclude`]
+    pub(crate) fn exclude_patterns(&self) -> Result<Vec<glob::Pattern>, FileCopyError> {
+        self.exclude
+            .iter()
+            .map(|pattern| {
+                glob::Pattern::new(pattern).map_err(|source| FileCopyError::Pattern {
+                    pattern: pattern.clone(),
+                    source,
+                })
+            })
+            .collect()
+    }
+
+    /// Find every directory and file under `dir`, relative to [`FileCopy::from`]
+    ///
+    /// Anything matching one of the `exclude` patterns is skipped.  Returns the total size of the
+    /// files found.
+    fn walk(
+        &self,
+        dir: &Path,
+        exclude: &[glob::Pattern],
+        visited: &mut Vec<PathBuf>,
+        dirs: &mut Vec<PathBuf>,
+        files: &mut Vec<PathBuf>,
+    ) -> Result<u64, FileCopyError> {
+        // following symlinks could otherwise loop forever
+        let canonical = dir.canonicalize()?;
+        if visited.contains(&canonical) {
+            return Ok(0);
+        }
+        visited.push(canonical);
+
+        let mut size = 0;
+        for entry in std::fs::read_dir(dir)? {
+            let path = entry?.path();
+            let metadata = if self.follow_symlinks {
+                std::fs::metadata(&path)?
+            } else {
+                let metadata = std::fs::symlink_metadata(&path)?;
+                if metadata.is_symlink() {
+                    continue;
+                }
+                metadata
+            };
+
+            let relative = path
+                .strip_prefix(&self.from)
+                .expect("walked paths are inside `from`")
+                .to_path_buf();
+            if exclude.iter().any(|p| p.matches_path(&relative)) {
+                continue;
+            }
+            if metadata.is_dir() {
+                dirs.push(relative);
+                size += self.walk(&path, exclude, visited, dirs, files)?;
+            } else if metadata.is_file() {
+                files.push(relative);
+                size += metadata.len();
+            }
+
+            if size > Self::MAX_DIRECTORY_SIZE {
+                return Err(FileCopyError::DirectoryTooLarge(self.from.clone()));
+            }
+        }
+        Ok(size)
+    }
+
+    /// Copy the directory [`FileCopy::from`] into `to`, keeping its structure
+    fn copy_dir(&self, to: &Path) -> Result<(), FileCopyError> {
+        if to.is_file() {
+            return Err(FileCopyError::DirectoryIntoFile {
+                from: self.from.clone(),
+                to: self.to.clone(),
+            });
+        }
+
+        let exclude = self.exclude_patterns()?;
+        let (mut dirs, mut files) = (Vec::new(), Vec::new());
+        self.walk(&self.from, &exclude, &mut Vec::new(), &mut dirs, &mut files)?;
+
+        std::fs::create_dir_all(to)?;
+        for dir in dirs {
+            std::fs::create_dir_all(to.join(dir))?;
+        }
+        for file in files {
+            std::fs::copy(self.from.join(&file), to.join(&file))?;
+        }
+        Ok(())
+    }
+
+    /// Get the files that will be copied
+    ///
+    /// If [`FileCopy::from`] is a glob pattern, this is every file that matches it, otherwise it
+    /// is just [`FileCopy::from`].  Either way, the paths are relative to the directory
+    /// containing the config, see [`Config::resolve_path`].
+    pub fn sources(&self, config: &Config) -> Result<Vec<PathBuf>, FileCopyError> {
+        self.resolve(config).resolved_sources()
+    }
+
+    /// [`FileCopy::sources`] for a copy which has already been resolved
+    fn resolved_sources(&self) -> Result<Vec<PathBuf>, FileCopyError> {
+        if !self.is_glob() {
+            return Ok(vec![self.from.clone()]);
+        }
+
+        let pattern = self.from.to_string_lossy();
+        let paths = glob::glob(&pattern).map_err(|source| FileCopyError::Pattern {
+            pattern: pattern.to_string(),
+            source,
+        })?;
+
+        let mut sources = Vec::new();
+        for path in paths {
+            let path = path.map_err(|e| std::io::Error::new(e.error().kind(), e.to_string()))?;
+            if path.is_file() {
+                sources.push(path);
+            }
+        }
+
+        if sources.is_empty() {
+            Err(FileCopyError::NoMatches(pattern.into_owned()))
+        } else {
+            Ok(sources)
+        }
+    }
+
+    /// Copy the file(s) into the directory in which the test is run
+    ///
+    /// If [`FileCopy::from`] is a glob pattern, [`FileCopy::to`] is treated as a directory into
+    /// which each matching file is copied, keeping its file name.  If [`FileCopy::from`] is a
+    /// directory, its contents are copied into [`FileCopy::to`].
+    ///
+    /// [`FileCopy::from`] is relative to the directory containing the config, see
+    /// [`Config::resolve_path`].
+    ///
+    /// Nothing is copied if [`FileCopy::to`] is absolute or would leave `test_dir` (e.g.,
+    /// `../file`), even if the config has not been validated.
+    pub fn copy_into(
+        &self,
+        config: &Config,
+        test_dir: impl AsRef<Path>,
+    ) -> Result<(), FileCopyError> {
+        if escapes_directory(&self.to) {
+            return Err(FileCopyError::UnsafeDestination(self.to.clone()));
+        }
+        self.resolve(config).copy_resolved(test_dir.as_ref())
+    }
+
+    /// Where each of `sources`, which match the glob pattern [`FileCopy::from`], is copied,
+    /// relative to the test directory
+    pub(crate) fn glob_destinations(
+        &self,
+        sources: Vec<PathBuf>,
+    ) -> Result<BTreeMap<PathBuf, PathBuf>, FileCopyError> {
+        let mut copies = BTreeMap::<PathBuf, PathBuf>::new();
+        for source in sources {
+            let name = source.file_name().expect("globs only match files");
+            let dest = self.to.join(name);
+            if let Some(first) = copies.get(&dest) {
+                return Err(FileCopyError::DuplicateDestination {
+                    first: first.clone(),
+                    second: source,
+                    to: dest,
+                });
+            }
+            copies.insert(dest, source);
+        }
+        Ok(copies)
+    }
+
+    /// [`FileCopy::copy_into`] for a copy which has already been resolved
+    fn copy_resolved(&self, test_dir: &Path) -> Result<(), FileCopyError> {
+        let to = test_dir.join(&self.to);
+        if !self.is_glob() && self.from.is_dir() {
+            self.copy_dir(&to)?;
+        } else if self.is_glob() {
+            // check every destination before copying anything, so nothing is overwritten
+            let copies = self.glob_destinations(self.resolved_sources()?)?;
+            std::fs::create_dir_all(&to)?;
+            for (dest, source) in copies {
+                std::fs::copy(&source, test_dir.join(dest))?;
+            }
+        } else {
+            if let Some(parent) = to.parent() {
+                std::fs::create_dir_all(parent)?;
+            }
+            std::fs::copy(&self.from, &to)?;
+        }
+
+        Ok(())
+    }
+}
@@ -3,7 +3,7 @@ use std::collections::HashSet;
 use std::fmt;
 use std::ops::{Deref, DerefMut};
 
-use serde::de::{Deserializer, MapAccess, Visitor};
+use serde::de::{value::MapAccessDeserializer, Deserializer, MapAccess, Visitor};
 use serde::ser::{SerializeMap, Serializer};
 use serde::{Deserialize, Serialize};
 
@@ -29,9 +29,142 @@ impl LanguageSet {
         }
     }
 
+    /// Add the built-in `language` at `version`, replacing any language with the same name
+    ///
+    /// Fails if `language` does not have `version` (see [`BuiltInLanguage::versions`]).
+    ///
+    /// ```
+    /// # use bedrock::language::{BuiltInLanguage, LanguageSet, Version};
+    /// let languages = LanguageSet::new()
+    ///     .with_builtin(BuiltInLanguage::Python3, Version::Latest)?
+    ///     .with_custom("ocaml", Some("ocamlc -o out solution.ml"), "./out", "solution.ml");
+    /// assert_eq!(2, languages.len());
+    /// # Ok::<(), bedrock::language::UnknownVersion>(())
+    /// ```
+    pub fn with_builtin(
+        self,
+        language: BuiltInLanguage,
+        version: Version,
+    ) -> Result<Self, UnknownVersion> {
+        if let (Version::Specific(v), Err(known)) = (&version, language.has_version(&version)) {
+            return Err(UnknownVersion {
+                language,
+                version: v.clone(),
+                known: known.into_iter().map(Into::into).collect(),
+            });
+        }
+        Ok(self.with(Language::BuiltIn { language, version }))
+    }
+
+    /// Add a custom language called `name`, replacing any language with the same name
+    ///
+    /// The language's display name is also `name`, and it has no `init` command.
+    pub fn with_custom(
+        self,
+        name: impl Into<String>,
+        build: Option<&str>,
+        run: impl Into<String>,
+        source_file: impl Into<String>,
+    ) -> Self {
+        let name = name.into();
+        self.with(Language::Custom {
+            raw_name: name.clone(),
+            name,
+            build: build.map(Into::into),
+            run: run.into(),
+            source_file: source_file.into(),
+            init: None,
+        })
+    }
+
+    fn with(mut self, language: Language) -> Self {
+        self.inner.retain(|l| l.raw_name() != language.raw_name());
+        self.inner.insert(language);
+        self
+    }
+
+    /// Find a language by the name it has in the config
+    ///
+    /// Built-in languages can also be found by one of their aliases, in any case (e.g., `py` or
+    /// `Python3` for `python3`), as they can be written in the config.
     pub fn get_by_str(&self, raw_name: &str) -> Option<&Language> {
-        self.inner.iter().find(|l| l.raw_name() == raw_name)
+        self.inner
+            .iter()
+            .find(|l| l.raw_name() == raw_name)
+            .or_else(|| {
+                let builtin = super::lookup(raw_name)?.builtin;
+                self.inner.iter().find(
+                    |l| matches!(l, Language::BuiltIn { language, .. } if *language == builtin),
+                )
+            })
     }
+
+    /// The languages which have a compile step, see [`Language::build_command`]
+    pub fn compiled(&self) -> impl Iterator<Item = &Language> {
+        self.inner.iter().filter(|l| l.build_command().is_some())
+    }
+
+    /// The languages which are run without being compiled first, see
+    /// [`Language::build_command`]
+    pub fn interpreted(&self) -> impl Iterator<Item = &Language> {
+        self.inner.iter().filter(|l| l.build_command().is_none())
+    }
+
+    /// Add all of the languages from `other` into this set
+    ///
+    /// Languages that are in both sets with the same definition are merged silently.  If any
+    /// language in `other` has the same name as a language in this set but a different
+    /// definition, every such conflict is returned and this set is left unchanged.
+    pub fn merge(&mut self, other: LanguageSet) -> Result<(), Vec<MergeConflict>> {
+        let mut conflicts = other
+            .inner
+            .iter()
+            .filter_map(|theirs| {
+                let ours = self.get_by_str(theirs.raw_name())?;
+                (ours != theirs).then(|| MergeConflict {
+                    name: theirs.raw_name().into(),
+                    ours: ours.clone(),
+                    theirs: theirs.clone(),
+                })
+            })
+            .collect::<Vec<_>>();
+
+        if !conflicts.is_empty() {
+            conflicts.sort_by(|a, b| a.name.cmp(&b.name));
+            return Err(conflicts);
+        }
+
+        self.inner.extend(other.inner);
+        Ok(())
+    }
+}
+
+/// A language which has different definitions in two [`LanguageSet`]s that are being merged
+#[derive(Debug, Clone, PartialEq, Eq, Hash, thiserror::Error)]
+#[error("Language '{name}' is defined differently in both sets")]
+pub struct MergeConflict {
+    /// The name of the language in the config
+    pub name: String,
+    /// The definition in the set being merged into
+    pub ours: Language,
+    /// The definition in the set being merged from
+    pub theirs: Language,
+}
+
+/// A version of a built-in language which is not supported, see [`LanguageSet::with_builtin`]
+#[derive(Debug, Clone, PartialEq, Eq, Hash, thiserror::Error)]
+#[error(
+    "Unknown {} version: '{version}'.  Known versions: {}",
+    language.as_str(),
+    known.join(", ")
+)]
+pub struct UnknownVersion {
+    /// The language that was requested
+    pub language: BuiltInLanguage,
+    /// The version that was requested
+    pub version: String,
+    /// The versions of `language` that are supported, see [`BuiltInLanguage::versions`]
+    pub known: Vec<String>,
 }
 
 impl Deref for LanguageSet {
@@ -106,15 +239,23 @@ impl<'de> Visitor<'de> for LanguageMapVisitor {
                     build,
                     run,
                     source_file,
+                    init,
                 } => Language::Custom {
                     name: name.unwrap_or_else(|| key.clone()).into_owned(),
                     raw_name: key.into_owned(),
                     build: build.map(Cow::into_owned),
                     run: run.into_owned(),
                     source_file: source_file.into_owned(),
+                    init: init.map(Cow::into_owned),
                 },
             };
 
+            if map.get_by_str(val.raw_name()).is_some() {
+                return Err(serde::de::Error::custom(format!(
+                    "Language '{}' is declared more than once",
+                    val.raw_name()
+                )));
+            }
             map.insert(val);
         }
 
@@ -131,13 +272,18 @@ impl<'de> Deserialize<'de> for LanguageSet {
     }
 }
 
+/// Languages are serialised as a map from name to definition, in alphabetical order of name
 impl Serialize for LanguageSet {
     fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
     where
         S: Serializer,
     {
-        let mut map = serializer.serialize_map(Some(self.inner.len()))?;
-        for lang in &self.inner {
+        // the inner set has no meaningful order, so sort by name to keep the output stable
+        let mut languages = self.inner.iter().collect::<Vec<_>>();
+        languages.sort_by_key(|l| l.raw_name());
+
+        let mut map = serializer.serialize_map(Some(languages.len()))?;
+        for lang in languages {
             match lang {
                 Language::BuiltIn {
                     language: name,
@@ -151,6 +297,7 @@ impl Serialize for LanguageSet {
                     build,
                     run,
                     source_file,
+                    init,
                 } => {
                     map.serialize_entry(
                         raw_name,
@@ -159,6 +306,7 @@ impl Serialize for LanguageSet {
                             build: build.as_ref().map(Into::into),
                             run: run.into(),
                             source_file: source_file.into(),
+                            init: init.as_ref().map(Into::into),
                         },
                     )?;
                 }
@@ -169,10 +317,9 @@ impl Serialize for LanguageSet {
 }
 
 /// Language as represented in the toml file
-#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq, Ord, PartialOrd, Hash)]
-#[serde(rename_all = "kebab-case", deny_unknown_fields)]
+#[derive(Serialize, Debug, Clone, PartialEq, Eq, Ord, PartialOrd, Hash)]
+#[serde(rename_all = "kebab-case")]
 enum TomlLanguage<'a> {
-    #[serde(alias = "*")]
     Latest,
     #[serde(untagged)]
     Version(Cow<'a, str>),
@@ -183,9 +330,91 @@ enum TomlLanguage<'a> {
         build: Option<Cow<'a, str>>,
         run: Cow<'a, str>,
         source_file: Cow<'a, str>,
+        init: Option<Cow<'a, str>>,
     },
 }
 
+/// The fields of [`TomlLanguage::Custom`]
+///
+/// This is deserialised separately so that a misspelled or missing field is reported as such,
+/// rather than as the table not matching any kind of language.
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct TomlCustomLanguage<'a> {
+    name: Option<Cow<'a, str>>,
+    build: Option<Cow<'a, str>>,
+    run: Cow<'a, str>,
+    source_file: Cow<'a, str>,
+    init: Option<Cow<'a, str>>,
+}
+
+struct TomlLanguageVisitor;
+
+impl<'de> Visitor<'de> for TomlLanguageVisitor {
+    type Value = TomlLanguage<'de>;
+
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "\"latest\", a version, or a table with `run` and `source_file`"
+        )
+    }
+
+    fn visit_str<E>(self, v: &str) -> Result<Self::Value, E>
+    where
+        E: serde::de::Error,
+    {
+        Ok(match v {
+            "latest" | "*" => TomlLanguage::Latest,
+            v => TomlLanguage::Version(v.to_owned().into()),
+        })
+    }
+
+    // YAML and JSON configs may write versions as numbers, e.g., `java: 21`
+    fn visit_u64<E>(self, v: u64) -> Result<Self::Value, E>
+    where
+        E: serde::de::Error,
+    {
+        Ok(TomlLanguage::Version(v.to_string().into()))
+    }
+
+    fn visit_i64<E>(self, v: i64) -> Result<Self::Value, E>
+    where
+        E: serde::de::Error,
+    {
+        Ok(TomlLanguage::Version(v.to_string().into()))
+    }
+
+    fn visit_map<M>(self, access: M) -> Result<Self::Value, M::Error>
+    where
+        M: MapAccess<'de>,
+    {
+        let TomlCustomLanguage {
+            name,
+            build,
+            run,
+            source_file,
+            init,
+        } = TomlCustomLanguage::deserialize(MapAccessDeserializer::new(access))?;
+        Ok(TomlLanguage::Custom {
+            name,
+            build,
+            run,
+            source_file,
+            init,
+        })
+    }
+}
+
+impl<'de> Deserialize<'de> for TomlLanguage<'de> {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        deserializer.deserialize_any(TomlLanguageVisitor)
+    }
+}
+
 impl<'a> From<&'a Version> for TomlLanguage<'a> {
     fn from(value: &'a Version) -> Self {
         match value {
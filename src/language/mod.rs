@@ -1,9 +1,11 @@
 mod language_set;
-pub use language_set::LanguageSet;
+pub use language_set::{LanguageSet, MergeConflict, UnknownVersion};
 
 use phf::{phf_map, phf_ordered_map};
 use serde::{Deserialize, Serialize};
-use std::str::FromStr;
+use std::{cmp::Ordering, str::FromStr};
+
+use crate::CommandConfig;
 use strum::VariantNames;
 
 struct LanguageVersion {
@@ -83,6 +85,23 @@ static BUILTINS: phf::Map<&'static str, Builtin> = phf_map! {
     },
 };
 
+/// Other names that users commonly write for built-in languages, mapped to the key in `BUILTINS`
+static ALIASES: phf::Map<&'static str, &'static str> = phf_map! {
+    "py" => "python3",
+    "python" => "python3",
+    "js" => "javascript",
+    "node" => "javascript",
+    "rs" => "rust",
+};
+
+/// Find a built-in language by its name or one of its aliases, ignoring case
+fn lookup(name: &str) -> Option<&'static Builtin> {
+    let name = name.to_lowercase();
+    BUILTINS
+        .get(&name)
+        .or_else(|| ALIASES.get(&name).map(|name| &BUILTINS[*name]))
+}
+
 #[derive(Debug, Copy, Clone, PartialEq, Eq, Ord, PartialOrd, Hash, VariantNames)]
 #[strum(serialize_all = "lowercase")]
 pub enum BuiltInLanguage {
@@ -93,6 +112,24 @@ pub enum BuiltInLanguage {
 }
 
 impl BuiltInLanguage {
+    /// Every built-in language
+    pub const fn all() -> &'static [BuiltInLanguage] {
+        &[Self::Python3, Self::Java, Self::JavaScript, Self::Rust]
+    }
+
+    /// Other names which are accepted for this language (e.g., `py` for `python3`)
+    pub fn aliases(self) -> impl Iterator<Item = &'static str> {
+        ALIASES
+            .entries()
+            .filter(move |(_, name)| **name == self.as_str())
+            .map(|(alias, _)| *alias)
+    }
+
+    /// The versions of this language that are supported, from oldest to newest
+    pub fn versions(self) -> impl Iterator<Item = &'static str> {
+        BUILTINS[self.as_str()].versions.keys().copied()
+    }
+
     pub fn has_version(self, version: &Version) -> Result<(), Vec<&str>> {
         let bil = &BUILTINS[self.as_str()];
         match version {
@@ -140,7 +177,7 @@ impl BuiltInLanguage {
     pub fn build_command(self, version: &Version) -> Option<&str> {
         let bil = &BUILTINS[self.as_str()];
         match version {
-            Version::Latest => bil.versions.values().last()?.build,
+            Version::Latest => bil.versions.values().next_back()?.build,
             Version::Specific(v) => bil.versions[v].build,
         }
     }
@@ -151,7 +188,7 @@ impl BuiltInLanguage {
             Version::Latest => {
                 bil.versions
                     .values()
-                    .last()
+                    .next_back()
                     .expect("all language must have at least one version")
                     .run
             }
@@ -165,7 +202,7 @@ impl BuiltInLanguage {
             Version::Latest => {
                 bil.versions
                     .values()
-                    .last()
+                    .next_back()
                     .expect("all language must have at least one version")
                     .install_command
             }
@@ -179,7 +216,7 @@ impl BuiltInLanguage {
             Version::Latest => {
                 bil.versions
                     .values()
-                    .last()
+                    .next_back()
                     .expect("all language must have at least one version")
                     .init_command
             }
@@ -188,9 +225,16 @@ impl BuiltInLanguage {
     }
 }
 
+/// Accepts the name of a language or one of its aliases (e.g., `py`)
+///
+/// # Panics
+///
+/// If `value` is not a built-in language.  Use [`str::parse`] to handle unknown languages.
 impl From<&str> for BuiltInLanguage {
     fn from(value: &str) -> Self {
-        BUILTINS[value].builtin
+        lookup(value)
+            .unwrap_or_else(|| panic!("Unknown built-in language: '{}'", value))
+            .builtin
     }
 }
 
@@ -198,17 +242,77 @@ impl FromStr for BuiltInLanguage {
     type Err = ();
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
-        BUILTINS.get(s).map(|b| b.builtin).ok_or(())
+        lookup(s).map(|b| b.builtin).ok_or(())
     }
 }
 
-#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq, Ord, PartialOrd, Hash)]
+/// A version of a built-in language
+///
+/// This is (de)serialised as it is written in a config: `"latest"` (or `"*"`) for
+/// [`Version::Latest`], and the version itself (e.g., `"21"`) otherwise.  A whole number (e.g.,
+/// `21` in YAML or JSON) is also accepted as a version.
+#[derive(Debug, Clone, PartialEq, Eq, Ord, PartialOrd, Hash)]
 pub enum Version {
     Latest,
     Specific(String),
 }
 
-#[derive(Debug, Clone, PartialEq, Eq, Ord, PartialOrd, Hash)]
+impl Serialize for Version {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        match self {
+            Version::Latest => serializer.serialize_str("latest"),
+            Version::Specific(v) => serializer.serialize_str(v),
+        }
+    }
+}
+
+impl<'de> Deserialize<'de> for Version {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        deserializer.deserialize_any(VersionVisitor)
+    }
+}
+
+struct VersionVisitor;
+
+impl serde::de::Visitor<'_> for VersionVisitor {
+    type Value = Version;
+
+    fn expecting(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "\"latest\" or a version")
+    }
+
+    fn visit_str<E>(self, v: &str) -> Result<Self::Value, E>
+    where
+        E: serde::de::Error,
+    {
+        Ok(match v {
+            "latest" | "*" => Version::Latest,
+            _ => Version::Specific(v.to_owned()),
+        })
+    }
+
+    fn visit_u64<E>(self, v: u64) -> Result<Self::Value, E>
+    where
+        E: serde::de::Error,
+    {
+        Ok(Version::Specific(v.to_string()))
+    }
+
+    fn visit_i64<E>(self, v: i64) -> Result<Self::Value, E>
+    where
+        E: serde::de::Error,
+    {
+        Ok(Version::Specific(v.to_string()))
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
 pub enum Language {
     BuiltIn {
         language: BuiltInLanguage,
@@ -220,9 +324,64 @@ pub enum Language {
         build: Option<String>,
         run: String,
         source_file: String,
+        /// Command to run before starting the server, see [`Language::init_command`]
+        init: Option<String>,
     },
 }
 
+/// Languages are ordered by [`Language::raw_name`], so custom and built-in languages are sorted
+/// together
+///
+/// Different definitions with the same name (which [`LanguageSet::merge`] reports as a conflict)
+/// are ordered built-in first, and then by their definitions.
+impl Ord for Language {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.raw_name()
+            .cmp(other.raw_name())
+            .then_with(|| match (self, other) {
+                (
+                    Language::BuiltIn { language, version },
+                    Language::BuiltIn {
+                        language: other_language,
+                        version: other_version,
+                    },
+                ) => (language, version).cmp(&(other_language, other_version)),
+                (Language::BuiltIn { .. }, Language::Custom { .. }) => Ordering::Less,
+                (Language::Custom { .. }, Language::BuiltIn { .. }) => Ordering::Greater,
+                (
+                    Language::Custom {
+                        name,
+                        build,
+                        run,
+                        source_file,
+                        init,
+                        ..
+                    },
+                    Language::Custom {
+                        name: other_name,
+                        build: other_build,
+                        run: other_run,
+                        source_file: other_source_file,
+                        init: other_init,
+                        ..
+                    },
+                ) => (name, build, run, source_file, init).cmp(&(
+                    other_name,
+                    other_build,
+                    other_run,
+                    other_source_file,
+                    other_init,
+                )),
+            })
+    }
+}
+
+impl PartialOrd for Language {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
 impl Language {
     pub fn raw_name(&self) -> &str {
         match self {
@@ -245,6 +404,13 @@ impl Language {
         }
     }
 
+    /// The extension of [`Language::source_file`] (e.g. `py` for `solution.py`), if it has one
+    pub fn extension(&self) -> Option<&str> {
+        std::path::Path::new(self.source_file())
+            .extension()
+            .and_then(|e| e.to_str())
+    }
+
     pub fn build_command(&self) -> Option<&str> {
         match self {
             Language::BuiltIn { language, version } => language.build_command(version),
@@ -259,6 +425,32 @@ impl Language {
         }
     }
 
+    /// A one-line summary of how this language is built and run, for displaying in UIs
+    ///
+    /// e.g., `build: javac Solution.java | run: java Solution`
+    pub fn command_summary(&self) -> String {
+        match self.build_command() {
+            Some(build) => format!("build: {} | run: {}", build, self.run_command()),
+            None => format!("run: {}", self.run_command()),
+        }
+    }
+
+    /// The commands for this language in the shape [leucite](https://basalt-rs.github.io/erudite/erudite/struct.CommandConfig.html)
+    /// expects
+    ///
+    /// Compiled languages are [`CommandConfig::Each`], and interpreted languages (with no
+    /// [`build_command`](Language::build_command)) are [`CommandConfig::Run`].
+    pub fn to_command_config(&self) -> CommandConfig<String> {
+        let run = self.run_command().to_string();
+        match self.build_command() {
+            Some(compile) => CommandConfig::Each {
+                compile: compile.to_string(),
+                run,
+            },
+            None => CommandConfig::Run { run },
+        }
+    }
+
     pub fn install_command(&self) -> Option<&str> {
         match self {
             Language::BuiltIn { language, version } => language.install_command(version),
@@ -269,7 +461,7 @@ impl Language {
     pub fn init_command(&self) -> Option<&str> {
         match self {
             Language::BuiltIn { language, version } => language.init_command(version),
-            Language::Custom { .. } => None,
+            Language::Custom { init, .. } => init.as_deref(),
         }
     }
 }
@@ -1,21 +1,45 @@
-use std::{io::Read, path::PathBuf, time::Duration};
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    fmt::Display,
+    io::Read,
+    num::NonZeroUsize,
+    ops::Range,
+    path::{Path, PathBuf},
+    time::Duration,
+};
 
-use language::LanguageSet;
+use language::{Language, LanguageSet};
 use miette::{Diagnostic, LabeledSpan, NamedSource, SourceCode};
 use packet::Packet;
+#[cfg(feature = "render")]
+use render::{RenderOptions, RenderPdfError};
 use roi::RawOrImport;
 use serde::{Deserialize, Serialize};
-use typst::foundations::Array;
+#[cfg(feature = "render")]
+use typst::foundations::{Array, Datetime, Dict, IntoValue, Value};
 use xxhash_rust::xxh3;
 
+mod builder;
+mod competitor;
 mod custom_serde;
+mod diff;
+mod file_copy;
 pub mod language;
 pub mod packet;
 pub mod render;
 pub mod roi;
+mod summary;
+pub mod validate;
 
 mod util;
 
+pub use builder::ConfigBuilder;
+pub use competitor::CompetitorConfig;
+pub use diff::ConfigDiff;
+pub use file_copy::FileCopyError;
+pub use summary::ConfigSummary;
+pub use util::HashOptions;
+
 #[cfg(test)]
 mod tests;
 
@@ -45,34 +69,155 @@ pub struct Accounts {
     pub competitors: Vec<User>,
 }
 
+/// Whether a [`User`] is an admin or a competitor, see [`Accounts`]
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Ord, PartialOrd, Hash)]
+pub enum Role {
+    Admin,
+    Competitor,
+}
+
+impl Accounts {
+    /// Every user along with their role, admins first
+    pub fn iter_with_roles(&self) -> impl Iterator<Item = (Role, &User)> {
+        let admins = self.admins.iter().map(|u| (Role::Admin, u));
+        let competitors = self.competitors.iter().map(|u| (Role::Competitor, u));
+        admins.chain(competitors)
+    }
+}
+
 /// Configuration for setting up the docker container and starting the server
 #[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq, Ord, PartialOrd, Hash, Default)]
 #[serde(deny_unknown_fields)]
 pub struct Setup {
     /// Specifies what commands are to be run when building the container to ensure dependencies
     /// are installed.
+    ///
+    /// This may also be a list of commands, which are joined with newlines.
+    #[serde(default, deserialize_with = "custom_serde::script::deserialize")]
     pub install: Option<RawOrImport<String, roi::Raw>>,
     /// Specifies commands to run before running basalt-server so that dependencies are enabled
     /// properly.
+    ///
+    /// This may also be a list of commands, which are joined with newlines.
+    #[serde(default, deserialize_with = "custom_serde::script::deserialize")]
     pub init: Option<RawOrImport<String, roi::Raw>>,
 }
 
-#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq, Ord, PartialOrd, Hash)]
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq, Ord, PartialOrd, Hash, Default)]
 #[serde(deny_unknown_fields)]
 pub struct FileCopy {
     /// Source file to copy
     ///
-    /// Relative to the directory in which the server is running
+    /// This may be a glob pattern (e.g. `fixtures/*.txt`), in which case `to` is the directory
+    /// into which all of the matching files are copied.
+    ///
+    /// This may also be a directory, in which case the whole tree is copied into `to`, which must
+    /// also be a directory.  The tree may be at most [`FileCopy::MAX_DIRECTORY_SIZE`] bytes.
+    ///
+    /// Relative to the directory containing the config file, see [`Config::resolve_path`]
     pub from: PathBuf,
     /// Destination of the file
     ///
     /// Relative to the directory in which the test is run
     pub to: PathBuf,
+    /// Whether symbolic links inside a directory being copied are followed
+    ///
+    /// If this is false, symbolic links are skipped.
+    ///
+    /// [Default: false]
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub follow_symlinks: bool,
+    /// Glob patterns (e.g., `.git` or `*.tmp`) for files and directories that are skipped when
+    /// copying a directory
+    ///
+    /// Relative to [`FileCopy::from`].  Excluding a directory skips everything inside it.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub exclude: Vec<String>,
 }
 
-/// Mirrors the `CommandConfig` type in [leucite](https://basalt-rs.github.io/erudite/erudite/struct.CommandConfig.html)
+/// Configuration for rendering the packet to a PDF
 #[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq, Ord, PartialOrd, Hash, Default)]
-#[serde(deny_unknown_fields, untagged)]
+#[serde(deny_unknown_fields)]
+pub struct RenderConfig {
+    /// Font files (TTF/OTF) to make available to the template
+    ///
+    /// Relative to the directory containing the config file, see [`Config::resolve_path`]
+    #[serde(default)]
+    pub fonts: Vec<PathBuf>,
+    /// The paper size used by the default template (e.g., `a4` or `letter`), see
+    /// [typst's `page` function](https://typst.app/docs/reference/layout/page/#parameters-paper)
+    pub page_size: Option<String>,
+    /// The margin around each page used by the default template, as a typst length (e.g.,
+    /// `2.5cm`)
+    pub margin: Option<String>,
+    /// The number of columns on each page used by the default template
+    pub columns: Option<NonZeroUsize>,
+    /// Whether the default template includes a table of contents listing each problem
+    #[serde(default)]
+    pub toc: bool,
+}
+
+#[cfg(feature = "render")]
+impl RenderConfig {
+    /// The arguments for `#set page(..)` described by this config, which are defined as
+    /// `#page-setup` in the template
+    pub(crate) fn page_setup(&self, world: &dyn typst::World) -> std::io::Result<Dict> {
+        use comemo::Track;
+        use std::io::{Error, ErrorKind};
+        use typst::layout::Paper;
+
+        let mut dict = Dict::new();
+        if let Some(size) = &self.page_size {
+            // typst calls US paper sizes `us-letter` etc.
+            let paper = size
+                .parse::<Paper>()
+                .or_else(|_| format!("us-{}", size).parse::<Paper>())
+                .map_err(|_| {
+                    Error::new(
+                        ErrorKind::InvalidInput,
+                        format!("Unknown page size: '{}'", size),
+                    )
+                })?;
+            dict.insert("paper".into(), paper.into_value());
+        }
+        if let Some(margin) = &self.margin {
+            let value = typst::eval::eval_string(
+                world.track(),
+                margin,
+                typst::syntax::Span::detached(),
+                typst::eval::EvalMode::Code,
+                Default::default(),
+            );
+            match value {
+                Ok(v @ (Value::Length(_) | Value::Ratio(_) | Value::Relative(_))) => {
+                    dict.insert("margin".into(), v);
+                }
+                _ => {
+                    return Err(Error::new(
+                        ErrorKind::InvalidInput,
+                        format!("Invalid margin: '{}'", margin),
+                    ))
+                }
+            }
+        }
+        if let Some(columns) = self.columns {
+            dict.insert("columns".into(), Value::Int(columns.get() as i64));
+        }
+        Ok(dict)
+    }
+}
+
+/// Mirrors the `CommandConfig` type in [leucite](https://basalt-rs.github.io/erudite/erudite/struct.CommandConfig.html)
+///
+/// In the config, this may be written as:
+///
+/// - a single value (e.g., `max_memory = 256`), which is [`CommandConfig::Both`]
+/// - a table with `compile` and/or `run` (e.g., `max_memory = { run = 256 }`), which is
+///   [`CommandConfig::Compile`], [`CommandConfig::Run`], or [`CommandConfig::Each`] depending on
+///   which keys are present
+/// - an empty table, or leaving it out entirely, which is [`CommandConfig::Neither`]
+#[derive(Serialize, Debug, Clone, PartialEq, Eq, Ord, PartialOrd, Hash, Default)]
+#[serde(untagged)]
 pub enum CommandConfig<T> {
     #[default]
     Neither,
@@ -89,7 +234,52 @@ pub enum CommandConfig<T> {
     },
 }
 
+/// [`CommandConfig`] as represented in the config file
+#[derive(Deserialize)]
+#[serde(
+    deny_unknown_fields,
+    untagged,
+    expecting = "a single value, or a table with `compile` and/or `run`"
+)]
+enum TomlCommandConfig<T> {
+    Neither,
+    Phases { compile: Option<T>, run: Option<T> },
+    Both(T),
+}
+
+impl<'de, T> Deserialize<'de> for CommandConfig<T>
+where
+    T: Deserialize<'de>,
+{
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        Ok(match TomlCommandConfig::deserialize(deserializer)? {
+            TomlCommandConfig::Neither => CommandConfig::Neither,
+            TomlCommandConfig::Both(t) => CommandConfig::Both(t),
+            TomlCommandConfig::Phases { compile, run } => match (compile, run) {
+                (None, None) => CommandConfig::Neither,
+                (Some(compile), None) => CommandConfig::Compile { compile },
+                (None, Some(run)) => CommandConfig::Run { run },
+                (Some(compile), Some(run)) => CommandConfig::Each { compile, run },
+            },
+        })
+    }
+}
+
+/// A phase of running a test
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Ord, PartialOrd, Hash)]
+pub enum Phase {
+    Compile,
+    Run,
+}
+
 impl<T> CommandConfig<T> {
+    pub fn is_neither(&self) -> bool {
+        matches!(self, CommandConfig::Neither)
+    }
+
     pub fn compile(&self) -> Option<&T> {
         match self {
             CommandConfig::Neither => None,
@@ -109,6 +299,127 @@ impl<T> CommandConfig<T> {
             CommandConfig::Each { run, .. } => Some(run),
         }
     }
+
+    pub fn get(&self, phase: Phase) -> Option<&T> {
+        match phase {
+            Phase::Compile => self.compile(),
+            Phase::Run => self.run(),
+        }
+    }
+
+    pub fn as_ref(&self) -> CommandConfig<&T> {
+        match self {
+            CommandConfig::Neither => CommandConfig::Neither,
+            CommandConfig::Both(t) => CommandConfig::Both(t),
+            CommandConfig::Compile { compile } => CommandConfig::Compile { compile },
+            CommandConfig::Run { run } => CommandConfig::Run { run },
+            CommandConfig::Each { compile, run } => CommandConfig::Each { compile, run },
+        }
+    }
+
+    pub fn map<U, F>(self, mut f: F) -> CommandConfig<U>
+    where
+        F: FnMut(T) -> U,
+    {
+        match self {
+            CommandConfig::Neither => CommandConfig::Neither,
+            CommandConfig::Both(t) => CommandConfig::Both(f(t)),
+            CommandConfig::Compile { compile } => CommandConfig::Compile {
+                compile: f(compile),
+            },
+            CommandConfig::Run { run } => CommandConfig::Run { run: f(run) },
+            CommandConfig::Each { compile, run } => CommandConfig::Each {
+                compile: f(compile),
+                run: f(run),
+            },
+        }
+    }
+}
+
+/// Settings for the test runner that only apply to a single language
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq, Ord, PartialOrd, Hash, Default)]
+#[serde(deny_unknown_fields)]
+pub struct LanguageOverride {
+    /// The amount of time that the compile and/or run phase may take for this language, replacing
+    /// [`TestRunner::timeout`] for that phase
+    ///
+    /// Measured in milliseconds
+    #[serde(rename = "timeout_ms")]
+    #[serde(with = "custom_serde::command_duration", default)]
+    #[serde(skip_serializing_if = "CommandConfig::is_neither")]
+    pub timeout: CommandConfig<Duration>,
+}
+
+/// How the output of a test is compared with its expected output, see [`TestRunner::comparison`]
+///
+/// In the config, this is written as `"exact"`, `"trimmed"`, or `"tokens"`.
+#[derive(Serialize, Debug, Copy, Clone, PartialEq, Eq, Ord, PartialOrd, Hash, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum ComparisonMode {
+    /// The outputs must be identical
+    Exact,
+    /// Leading and trailing whitespace is ignored, so the output of `hello world    ` matches the
+    /// expected output of ` hello world`
+    #[default]
+    Trimmed,
+    /// The outputs are split on whitespace, and must have the same tokens, so the output of
+    /// `1  2\n` matches the expected output of `1 2`
+    Tokens,
+}
+
+impl ComparisonMode {
+    /// Apply this comparison to `output`, so that two outputs match exactly when the results
+    /// are equal
+    pub(crate) fn apply(self, output: &str) -> String {
+        match self {
+            ComparisonMode::Exact => output.to_string(),
+            ComparisonMode::Trimmed => output.trim().to_string(),
+            ComparisonMode::Tokens => output.split_whitespace().collect::<Vec<_>>().join(" "),
+        }
+    }
+}
+
+/// Also accepts a bool, which is how `trim_output` used to be written
+impl<'de> Deserialize<'de> for ComparisonMode {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        struct ComparisonModeVisitor;
+
+        impl serde::de::Visitor<'_> for ComparisonModeVisitor {
+            type Value = ComparisonMode;
+
+            fn expecting(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+                write!(f, "\"exact\", \"trimmed\", or \"tokens\"")
+            }
+
+            fn visit_bool<E>(self, v: bool) -> Result<Self::Value, E>
+            where
+                E: serde::de::Error,
+            {
+                Ok(if v {
+                    ComparisonMode::Trimmed
+                } else {
+                    ComparisonMode::Exact
+                })
+            }
+
+            fn visit_str<E>(self, v: &str) -> Result<Self::Value, E>
+            where
+                E: serde::de::Error,
+            {
+                match v {
+                    "exact" => Ok(ComparisonMode::Exact),
+                    "trimmed" => Ok(ComparisonMode::Trimmed),
+                    "tokens" => Ok(ComparisonMode::Tokens),
+                    _ => Err(E::unknown_variant(v, &["exact", "trimmed", "tokens"])),
+                }
+            }
+        }
+
+        deserializer.deserialize_any(ComparisonModeVisitor)
+    }
 }
 
 /// Configuration for the test runner
@@ -127,24 +438,42 @@ pub struct TestRunner {
         default = "TestRunner::default_timeout"
     )]
     pub timeout: Duration,
-    /// Whether the test runner should trim the output of a test before comparing with the
-    /// expected output
+    /// How the output of a test is compared with the expected output, see [`ComparisonMode`]
+    ///
+    /// This used to be `trim_output`, which is still accepted: `trim_output = true` is
+    /// [`ComparisonMode::Trimmed`] and `trim_output = false` is [`ComparisonMode::Exact`].
     ///
-    /// If this is true, the output of `hello world    ` matches the expected output of ` hello
-    /// world`
+    /// [Default: `trimmed`]
+    #[serde(default, alias = "trim_output")]
+    pub comparison: ComparisonMode,
+    /// Whether `\r\n` should be treated the same as `\n` when comparing the output of a test with
+    /// the expected output
+    ///
+    /// Line endings are normalised before the [`TestRunner::comparison`] is applied.
     ///
     /// [Default: true]
-    #[serde(default = "TestRunner::default_trim_output")]
-    pub trim_output: bool,
+    #[serde(default = "TestRunner::default_normalize_line_endings")]
+    pub normalize_line_endings: bool,
+    /// Whether the output of a test should be compared with the expected output ignoring case
+    ///
+    /// If this is true, the output of `YES` matches the expected output of `yes`.  Case is
+    /// ignored after line endings are normalised and the [`TestRunner::comparison`] is applied.
+    ///
+    /// [Default: false]
+    #[serde(default)]
+    pub case_insensitive: bool,
     /// Files to copy into the test directory
     #[serde(default)]
     pub copy_files: Vec<FileCopy>,
     /// Amount of memory that may be used by the process, measured in MiB
-    #[serde(default)]
+    #[serde(default, skip_serializing_if = "CommandConfig::is_neither")]
     pub max_memory: CommandConfig<u64>,
     /// Maximum size of files that may be created by the tests, measured in MiB
-    #[serde(default)]
+    #[serde(default, skip_serializing_if = "CommandConfig::is_neither")]
     pub max_file_size: CommandConfig<u64>,
+    /// Overrides for specific languages, keyed by the name of the language in the config
+    #[serde(default)]
+    pub language_overrides: BTreeMap<String, LanguageOverride>,
 }
 
 impl TestRunner {
@@ -152,19 +481,57 @@ impl TestRunner {
         Duration::from_secs(10)
     }
 
-    fn default_trim_output() -> bool {
+    fn default_normalize_line_endings() -> bool {
         true
     }
+
+    /// Whether `actual` is the expected output of `test`, using the comparison settings of this
+    /// test runner
+    pub fn output_matches<'a>(
+        &self,
+        test: &packet::Test,
+        actual: impl Into<packet::ActualOutput<'a>>,
+    ) -> bool {
+        test.matches(actual, self)
+    }
+
+    /// The memory limit for the given phase of a test in MiB, if there is one
+    ///
+    /// See [`TestRunner::max_memory`].
+    pub fn memory_for(&self, phase: Phase) -> Option<u64> {
+        self.max_memory.get(phase).copied()
+    }
+
+    /// The limit on the size of files created in the given phase of a test in MiB, if there is
+    /// one
+    ///
+    /// See [`TestRunner::max_file_size`].
+    pub fn file_size_for(&self, phase: Phase) -> Option<u64> {
+        self.max_file_size.get(phase).copied()
+    }
+
+    /// The timeout for the given phase of a test in `language`, taking
+    /// [`TestRunner::language_overrides`] into account
+    pub fn timeout_for(&self, language: &str, phase: Phase) -> Duration {
+        self.language_overrides
+            .get(language)
+            .and_then(|o| o.timeout.get(phase))
+            .copied()
+            .unwrap_or(self.timeout)
+    }
 }
 
 impl Default for TestRunner {
     fn default() -> Self {
         Self {
             timeout: Self::default_timeout(),
-            trim_output: Self::default_trim_output(),
+            comparison: ComparisonMode::default(),
+            normalize_line_endings: Self::default_normalize_line_endings(),
+            case_insensitive: false,
             copy_files: Default::default(),
             max_memory: CommandConfig::Neither,
             max_file_size: CommandConfig::Neither,
+            language_overrides: Default::default(),
         }
     }
 }
@@ -178,6 +545,57 @@ pub enum ConfigReadError {
     #[error("{}", .0.to_string())] // needed to use the miette error instead of thiserror
     #[diagnostic(transparent)]
     MalformedData(miette::Error),
+    /// One of the required top-level sections is missing
+    #[error(transparent)]
+    #[diagnostic(transparent)]
+    MissingSection(#[from] MissingSectionError),
+}
+
+/// The sections which every config must have, with a minimal example of each
+const REQUIRED_SECTIONS: [(&str, &str); 3] = [
+    ("languages", "[languages]\npython3 = \"latest\""),
+    (
+        "accounts",
+        "[accounts]\nadmins = [{ name = \"admin\", password = \"...\" }]\ncompetitors = []",
+    ),
+    (
+        "packet",
+        "[packet]\ntitle = \"My Competition\"\nproblems = []",
+    ),
+];
+
+/// A required top-level section (`languages`, `accounts`, or `packet`) is missing from the config
+#[derive(Debug, thiserror::Error, Diagnostic)]
+#[error("The config is missing the required `{section}` section")]
+#[diagnostic(help("Every config needs a `{section}` section, for example:\n\n{example}"))]
+pub struct MissingSectionError {
+    /// The name of the missing section
+    pub section: &'static str,
+    example: &'static str,
+    #[source_code]
+    source_code: NamedSource<String>,
+    #[label("expected `{section}` in this config")]
+    span: (usize, usize),
+}
+
+impl MissingSectionError {
+    /// Check whether a deserialisation error `message` is about a missing section
+    fn from_message(
+        message: &str,
+        content: &str,
+        file_name: Option<impl AsRef<str>>,
+        language: &str,
+    ) -> Option<Self> {
+        let field = message.strip_prefix("missing field `")?.split('`').next()?;
+        let (section, example) = REQUIRED_SECTIONS.into_iter().find(|(s, _)| *s == field)?;
+        let name = file_name.as_ref().map_or("config", |n| n.as_ref());
+        Some(Self {
+            section,
+            example,
+            source_code: NamedSource::new(name, content.to_string()).with_language(language),
+            span: (0, 0),
+        })
+    }
 }
 
 impl ConfigReadError {
@@ -185,7 +603,14 @@ impl ConfigReadError {
     where
         S: SourceCode + 'static,
     {
-        let labels = if let Some(span) = value.span() {
+        Self::malformed_at(source, value.message(), value.span())
+    }
+
+    fn malformed_at<S>(source: S, message: impl Display, span: Option<Range<usize>>) -> Self
+    where
+        S: SourceCode + 'static,
+    {
+        let labels = if let Some(span) = span {
             vec![LabeledSpan::new_with_span(Some("here".into()), span)]
         } else {
             Vec::new()
@@ -193,19 +618,54 @@ impl ConfigReadError {
         Self::MalformedData(
             miette::miette! {
                 labels = labels,
-                "{}", value.message()
+                "{}", message
             }
             .with_source_code(source),
         )
     }
+
+    #[cfg(any(feature = "json", feature = "yaml"))]
+    fn malformed_in(
+        content: &str,
+        file_name: Option<impl AsRef<str>>,
+        language: &str,
+        message: impl Display,
+        span: Option<Range<usize>>,
+    ) -> Self {
+        if let Some(file_name) = file_name {
+            Self::malformed_at(
+                NamedSource::new(file_name, content.to_string()).with_language(language),
+                message,
+                span,
+            )
+        } else {
+            Self::malformed_at(content.to_string(), message, span)
+        }
+    }
+}
+
+/// The directory containing the config file called `file_name`, see [`Config::resolve_path`]
+fn config_dir(file_name: Option<impl AsRef<str>>) -> PathBuf {
+    file_name
+        .as_ref()
+        .and_then(|f| Path::new(f.as_ref()).parent())
+        .map(Path::to_path_buf)
+        .unwrap_or_default()
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
 #[serde(deny_unknown_fields)]
 pub struct Config {
-    /// Hash of the config file itself.  This is used for [`Config::hash`].
+    /// Hash of the contents of the config.  This is used for [`Config::hash`].
     #[serde(skip)]
     hash: u64,
+    /// The directory containing the config file, against which relative paths in the config
+    /// (e.g., [`RenderConfig::fonts`]) are resolved
+    ///
+    /// This is empty, so paths are relative to the directory in which the server is running,
+    /// when the config was not read from a file in another directory.
+    #[serde(skip)]
+    dir: PathBuf,
     /// Configuration for setting up the docker container and starting the server
     pub setup: Option<RawOrImport<Setup>>,
     /// Port on which the server will be hosted
@@ -220,34 +680,149 @@ pub struct Config {
     /// Configuration for the test runner
     #[serde(default)]
     pub test_runner: RawOrImport<TestRunner>,
+    /// Configuration for rendering the packet
+    #[serde(default)]
+    pub render: RawOrImport<RenderConfig>,
+    /// When the competition starts, as an RFC 3339 string (e.g., `"2025-03-01T09:00:00-05:00"`)
+    ///
+    /// See [`Config::is_active`].
+    #[serde(
+        default,
+        with = "time::serde::rfc3339::option",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub start_time: Option<time::OffsetDateTime>,
+    /// When the competition ends, as an RFC 3339 string
+    ///
+    /// This must not be before [`Config::start_time`].  See [`Config::is_active`].
+    #[serde(
+        default,
+        with = "time::serde::rfc3339::option",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub end_time: Option<time::OffsetDateTime>,
 }
 
 impl Config {
     /// Read config from a string
     ///
-    /// - `file_name` provided for better miette errors
+    /// - `file_name` provided for better miette errors, and to resolve relative paths in the
+    ///   config against the directory containing it
     pub fn from_str(
         content: impl AsRef<str>,
         file_name: Option<impl AsRef<str>>,
     ) -> Result<Self, ConfigReadError> {
         let content = content.as_ref();
-        let mut config: Self = toml_edit::de::from_str(content).map_err(|e| {
-            if let Some(file_name) = file_name {
-                ConfigReadError::malformed(
-                    NamedSource::new(file_name, content.to_string()).with_language("TOML"),
-                    e,
-                )
-            } else {
-                ConfigReadError::malformed(content.to_string(), e)
-            }
-        })?;
-        config.hash = xxh3::xxh3_64(content.as_bytes());
+        let dir = config_dir(file_name.as_ref());
+        let mut config: Self =
+            roi::deserialize_with_imports(&dir, || toml_edit::de::from_str(content)).map_err(
+                |(e, import)| {
+                    if let Some(err) = import {
+                        return err;
+                    }
+                    if let Some(missing) = MissingSectionError::from_message(
+                        e.message(),
+                        content,
+                        file_name.as_ref(),
+                        "TOML",
+                    ) {
+                        return missing.into();
+                    }
+                    if let Some(file_name) = file_name {
+                        ConfigReadError::malformed(
+                            NamedSource::new(file_name, content.to_string()).with_language("TOML"),
+                            e,
+                        )
+                    } else {
+                        ConfigReadError::malformed(content.to_string(), e)
+                    }
+                },
+            )?;
+        config.hash = config.canonical_hash_u64();
+        config.dir = dir;
+        Ok(config)
+    }
+
+    /// Read config from a JSON string
+    ///
+    /// - `file_name` provided for better miette errors, and to resolve relative paths in the
+    ///   config against the directory containing it
+    #[cfg(feature = "json")]
+    pub fn from_json_str(
+        content: impl AsRef<str>,
+        file_name: Option<impl AsRef<str>>,
+    ) -> Result<Self, ConfigReadError> {
+        let content = content.as_ref();
+        let dir = config_dir(file_name.as_ref());
+        let mut config: Self =
+            roi::deserialize_with_imports(&dir, || serde_json::from_str(content)).map_err(
+                |(e, import)| {
+                    if let Some(err) = import {
+                        return err;
+                    }
+                    let message = e.to_string();
+                    if let Some(missing) = MissingSectionError::from_message(
+                        &message,
+                        content,
+                        file_name.as_ref(),
+                        "JSON",
+                    ) {
+                        return missing.into();
+                    }
+                    let offset = util::offset_of(content, e.line(), e.column());
+                    ConfigReadError::malformed_in(
+                        content,
+                        file_name,
+                        "JSON",
+                        &e,
+                        Some(offset..offset),
+                    )
+                },
+            )?;
+        config.hash = config.canonical_hash_u64();
+        config.dir = dir;
+        Ok(config)
+    }
+
+    /// Read config from a YAML string
+    ///
+    /// - `file_name` provided for better miette errors, and to resolve relative paths in the
+    ///   config against the directory containing it
+    #[cfg(feature = "yaml")]
+    pub fn from_yaml_str(
+        content: impl AsRef<str>,
+        file_name: Option<impl AsRef<str>>,
+    ) -> Result<Self, ConfigReadError> {
+        let content = content.as_ref();
+        let dir = config_dir(file_name.as_ref());
+        let mut config: Self =
+            roi::deserialize_with_imports(&dir, || serde_yaml::from_str(content)).map_err(
+                |(e, import)| {
+                    if let Some(err) = import {
+                        return err;
+                    }
+                    let message = e.to_string();
+                    if let Some(missing) = MissingSectionError::from_message(
+                        &message,
+                        content,
+                        file_name.as_ref(),
+                        "YAML",
+                    ) {
+                        return missing.into();
+                    }
+                    let span = e.location().map(|l| l.index()..l.index());
+                    ConfigReadError::malformed_in(content, file_name, "YAML", &e, span)
+                },
+            )?;
+        config.hash = config.canonical_hash_u64();
+        config.dir = dir;
         Ok(config)
     }
 
     /// Read config from a file
     ///
-    /// - `file_name` provided for better miette errors
+    /// - `file_name` provided for better miette errors, and to resolve relative paths in the
+    ///   config against the directory containing it
     pub fn read<R>(
         reader: &mut R,
         file_name: Option<impl AsRef<str>>,
@@ -262,7 +837,8 @@ impl Config {
 
     /// Read config from a file asynchronously
     ///
-    /// - `file_name` provided for better miette errors
+    /// - `file_name` provided for better miette errors, and to resolve relative paths in the
+    ///   config against the directory containing it
     #[cfg(feature = "tokio")]
     pub async fn read_async<R>(
         reader: &mut R,
@@ -277,29 +853,209 @@ impl Config {
         Self::from_str(&buf, file_name)
     }
 
+    /// Resolve a `path` from the config, which is relative to the directory containing the
+    /// config file
+    pub fn resolve_path(&self, path: impl AsRef<Path>) -> PathBuf {
+        self.dir.join(path)
+    }
+
     /// Generate a hash string for this config
     ///
+    /// This depends only on the contents of the config, not on how the config file was written
+    /// (e.g., its formatting, comments, or the order of its tables), or on the order in which the
+    /// admins and competitors are listed.
+    ///
     /// ```
     /// # use bedrock::Config;
     /// # let config = Config::default();
     /// let hash = format!("Your hash is: {}", config.hash());
     /// ```
     pub fn hash(&self) -> String {
-        let mut hash = self.hash;
-        const N: u64 = 36;
-        const ALPHABET: [u8; N as usize] = *b"abcdefghijklmnopqrstuvwxyz0123456789";
-        let mut out = String::with_capacity(14);
-        loop {
-            let n = (hash % N) as usize;
-            hash /= N;
-            out.push(ALPHABET[n] as char);
-            if hash == 0 {
-                break;
+        util::encode_hash(self.hash)
+    }
+
+    /// Generate a hash string for this config, encoded according to `options`
+    ///
+    /// ```
+    /// # use bedrock::{Config, HashOptions};
+    /// # let config = Config::default();
+    /// let options = HashOptions::default().with_min_length(16);
+    /// assert_eq!(16, config.hash_with(&options).len());
+    /// ```
+    pub fn hash_with(&self, options: &HashOptions) -> String {
+        util::encode_hash_with(self.hash, options)
+    }
+
+    /// Hash the contents of this config, see [`Config::hash`]
+    pub(crate) fn canonical_hash_u64(&self) -> u64 {
+        let mut config = self.clone();
+        // only for hashing; the accounts stay in the order in which they were written
+        config.accounts.admins.sort_by(|a, b| a.name.cmp(&b.name));
+        config
+            .accounts
+            .competitors
+            .sort_by(|a, b| a.name.cmp(&b.name));
+        // `serde_json::Value` stores maps sorted by key, so this is independent of the iteration
+        // order of the `LanguageSet`
+        let value = serde_json::to_value(&config).expect("configs can be serialised to JSON");
+        let bytes = serde_json::to_vec(&value).expect("configs can be serialised to JSON");
+        xxh3::xxh3_64(&bytes)
+    }
+
+    /// Generate a hash string for the packet in this config
+    ///
+    /// Unlike [`Config::hash`], this only changes when the packet (including its problems and
+    /// tests) changes, so changes to other sections such as the accounts are not visible.
+    pub fn packet_hash(&self) -> String {
+        let packet =
+            serde_json::to_vec(&*self.packet).expect("packets can always be serialised to JSON");
+        util::encode_hash(xxh3::xxh3_64(&packet))
+    }
+
+    /// Generate the shell script that installs the dependencies for this competition
+    ///
+    /// This is the `install_command` of each language (without duplicates), followed by
+    /// [`Setup::install`].
+    pub fn install_script(&self) -> String {
+        self.script(
+            Language::install_command,
+            self.setup.as_ref().and_then(|s| s.install.as_deref()),
+        )
+    }
+
+    /// Generate the shell script that is run before starting the server
+    ///
+    /// This is the `init_command` of each language (without duplicates), followed by
+    /// [`Setup::init`].
+    pub fn init_script(&self) -> String {
+        self.script(
+            Language::init_command,
+            self.setup.as_ref().and_then(|s| s.init.as_deref()),
+        )
+    }
+
+    /// Join the command for each language, sorted by name, with `extra` at the end
+    fn script(&self, command: fn(&Language) -> Option<&str>, extra: Option<&String>) -> String {
+        let mut languages = self.languages.iter().collect::<Vec<_>>();
+        languages.sort_by_key(|l| l.raw_name());
+
+        let mut lines: Vec<&str> = Vec::new();
+        for cmd in languages.into_iter().filter_map(command) {
+            if !lines.contains(&cmd) {
+                lines.push(cmd);
+            }
+        }
+
+        if let Some(extra) = extra {
+            lines.push(extra.trim_end());
+        }
+
+        lines.join("\n")
+    }
+
+    /// Whether the competition is running at `now`, according to [`Config::start_time`] and
+    /// [`Config::end_time`]
+    ///
+    /// The start is inclusive and the end is exclusive.  A competition without a start (or
+    /// end) time has always started (or never ends).
+    pub fn is_active(&self, now: time::OffsetDateTime) -> bool {
+        let started = match self.start_time {
+            Some(start) => start <= now,
+            None => true,
+        };
+        let ended = matches!(self.end_time, Some(end) if end <= now);
+        started && !ended
+    }
+
+    /// How long the competition runs, if it has both a start and an end time
+    ///
+    /// This is `None` if the end is before the start, which [`Config::validate`] reports.
+    pub fn duration(&self) -> Option<Duration> {
+        let (start, end) = self.start_time.zip(self.end_time)?;
+        (end - start).try_into().ok()
+    }
+
+    /// Find the user with the given name, along with their role
+    pub fn find_user(&self, name: &str) -> Option<(Role, &User)> {
+        self.accounts
+            .iter_with_roles()
+            .find(|(_, user)| user.name == name)
+    }
+
+    /// The languages which may be used to solve `problem`, sorted by name
+    ///
+    /// This is every language in the config if [`Problem::languages`](packet::Problem::languages)
+    /// is not given, otherwise it is the languages that it lists.  Names which are not in the
+    /// config are skipped, and are reported by [`Config::validate`].
+    pub fn effective_languages(&self, problem: &packet::Problem) -> Vec<&Language> {
+        let mut languages = match &problem.languages {
+            None => self.languages.iter().collect::<Vec<_>>(),
+            Some(names) => names
+                .iter()
+                .filter_map(|name| self.languages.get_by_str(name))
+                .collect(),
+        };
+        languages.sort();
+        languages
+    }
+
+    /// Every file that this config depends on, e.g. so that it can be reloaded when they change
+    ///
+    /// This includes imported files, files from which tests are read, files copied by the test
+    /// runner, fonts, and problem templates, but not the config file itself.  The paths are
+    /// sorted and without duplicates.
+    pub fn dependency_paths(&self) -> Vec<PathBuf> {
+        let mut paths = BTreeSet::new();
+        let mut add = |path: Option<&Path>| {
+            if let Some(path) = path {
+                paths.insert(path.to_path_buf());
             }
+        };
+
+        if let Some(setup) = &self.setup {
+            add(setup.import_path());
+            add(setup.install.as_ref().and_then(|i| i.import_path()));
+            add(setup.init.as_ref().and_then(|i| i.import_path()));
         }
-        out
+        add(self.languages.import_path());
+        add(self.accounts.import_path());
+        add(self.test_runner.import_path());
+        add(self.render.import_path());
+        add(self.packet.import_path());
+        add(self.packet.preamble.as_ref().and_then(|p| p.import_path()));
+
+        for problem in &self.packet.problems {
+            add(problem.import_path());
+            add(problem.description.as_ref().and_then(|d| d.import_path()));
+            add(problem.solution.as_ref().and_then(|s| s.import_path()));
+            add(problem.checker.as_ref().and_then(|c| c.import_path()));
+            add(problem
+                .template
+                .as_ref()
+                .map(|t| self.resolve_path(t))
+                .as_deref());
+            for test in &problem.tests {
+                add(test.input_file.as_deref());
+                add(test.output_file.as_deref());
+            }
+        }
+
+        for copy in &self.test_runner.copy_files {
+            match copy.sources(self) {
+                Ok(sources) => sources.iter().for_each(|s| add(Some(s))),
+                Err(_) => add(Some(&self.resolve_path(&copy.from))),
+            }
+        }
+        for font in &self.render.fonts {
+            add(Some(&self.resolve_path(font)));
+        }
+
+        paths.into_iter().collect()
     }
+}
 
+#[cfg(feature = "render")]
+impl Config {
     /// Render the competition information to a PDF, either using a provided template (written in
     /// [typst](https://typst.app/)) or the default template
     ///
@@ -311,7 +1067,81 @@ impl Config {
     /// - `#title`: `str` - the title of the competition
     /// - `#preamble`: `content` - rendered markdown of the competition
     /// - `#problems`: `array<Dict>` - array of problems in the packet
-    pub fn render_pdf(&self, template: Option<String>) -> std::io::Result<Vec<u8>> {
+    /// - `#page-setup`: `Dict` - arguments for `#set page(..)` from the `[render]` section of the
+    ///   config
+    /// - `#toc`: `bool` - whether to include a table of contents, from `toc` in the `[render]`
+    ///   section of the config
+    ///
+    /// Problems with at least one visible test have an `example` field, with the `input` and
+    /// `output` of the first (see [`Problem::example_test`](packet::Problem::example_test)), for
+    /// showing a sample input and output.
+    ///
+    /// Problems with their own [`template`](packet::Problem::template) have a `body` field
+    /// containing that template's output, which should be shown instead of the usual layout.
+    pub fn render_pdf(&self, template: Option<String>) -> Result<Vec<u8>, RenderPdfError> {
+        self.render_pdf_with(template, &RenderOptions::default())
+    }
+
+    /// Render the competition information to a PDF, like [`Config::render_pdf`], with extra
+    /// options for the renderer
+    pub fn render_pdf_with(
+        &self,
+        template: Option<String>,
+        options: &RenderOptions,
+    ) -> Result<Vec<u8>, RenderPdfError> {
+        let world = self.typst_world(template, options)?;
+        let mut document = typst::compile(&world).output?;
+
+        // metadata set by the template using `#set document(...)` takes priority
+        let info = &mut document.info;
+        info.title
+            .get_or_insert_with(|| self.packet.title.as_str().into());
+        if info.author.is_empty() {
+            info.author
+                .extend(self.packet.author.as_deref().map(Into::into));
+        }
+        if info.keywords.is_empty() {
+            info.keywords
+                .extend(self.packet.keywords.iter().map(|k| k.as_str().into()));
+        }
+
+        let timestamp = options.time.and_then(|time| {
+            let time = time.to_offset(time::UtcOffset::UTC);
+            Datetime::from_ymd_hms(
+                time.year(),
+                time.month().into(),
+                time.day(),
+                time.hour(),
+                time.minute(),
+                time.second(),
+            )
+        });
+        let pdf_options = typst_pdf::PdfOptions {
+            timestamp,
+            ..Default::default()
+        };
+        Ok(typst_pdf::pdf(&document, &pdf_options)?)
+    }
+
+    /// Render the competition information to one SVG per page, e.g., for previewing the packet
+    /// on the web without a PDF viewer
+    ///
+    /// The template is the same as for [`Config::render_pdf`].
+    pub fn render_svg_pages(
+        &self,
+        template: Option<String>,
+    ) -> Result<Vec<String>, RenderPdfError> {
+        let world = self.typst_world(template, &RenderOptions::default())?;
+        let document = typst::compile(&world).output?;
+        Ok(document.pages.iter().map(typst_svg::svg).collect())
+    }
+
+    /// Create the world used to compile the template, with the competition information defined
+    pub(crate) fn typst_world(
+        &self,
+        template: Option<String>,
+        options: &RenderOptions,
+    ) -> Result<render::typst::TypstWrapperWorld, RenderPdfError> {
         let template = if let Some(template) = template {
             template
         } else {
@@ -325,16 +1155,41 @@ impl Config {
             }
         };
 
-        let mut world = render::typst::TypstWrapperWorld::new(template);
+        let mut world = render::typst::TypstWrapperWorld::with_fonts(template, &options.fonts);
+        if let Some(time) = options.time {
+            world = world.with_time(time);
+        }
+        #[cfg(feature = "packages")]
+        if let Some(packages) = &options.packages {
+            world = world.with_packages(packages);
+        }
+        for font in &self.render.fonts {
+            world.add_font_file(self.resolve_path(font))?;
+        }
 
         let mut errs = Vec::new();
-        let mut problems = Array::with_capacity(self.packet.problems.len());
-        for p in &self.packet.problems {
-            match p.as_value(&world) {
-                Ok(v) => problems.push(v),
-                Err(err) => errs.push(err),
+        let mut problems = Array::with_capacity(self.packet.problem_count());
+        let ordered = if options.sort_by_difficulty {
+            self.packet.sorted_by_difficulty()
+        } else {
+            self.packet.ordered_problems()
+        };
+        for p in ordered {
+            match p.as_value(&world, options.markdown()) {
+                Ok(mut v) => {
+                    if let Some(body) = p.render_template(&world, &v, &self.dir)? {
+                        if let Value::Dict(dict) = &mut v {
+                            dict.insert("body".into(), Value::Content(body));
+                        }
+                    }
+                    problems.push(v)
+                }
+                Err(err) => errs.push((p.title.clone(), err)),
             }
         }
+        if !errs.is_empty() {
+            return Err(RenderPdfError::Problems(errs));
+        }
 
         world
             .library
@@ -348,35 +1203,113 @@ impl Config {
             .scope_mut()
             .define("title", self.packet.title.as_str());
 
+        let page_setup = self.render.page_setup(&world)?;
+        world
+            .library
+            .global
+            .scope_mut()
+            .define("page-setup", page_setup);
+
+        world
+            .library
+            .global
+            .scope_mut()
+            .define("toc", self.render.toc);
+
         let preamble = self
             .packet
             .preamble
             .as_deref()
-            .map(|s| s.content(&world))
-            .transpose()?;
+            .map(|s| s.content_with(&world, options.markdown()))
+            .transpose()
+            .map_err(RenderPdfError::Preamble)?;
         world
             .library
             .global
             .scope_mut()
             .define("preamble", preamble);
 
-        let document = typst::compile(&world)
-            .output
-            .expect("Error compiling typst");
-        typst_pdf::pdf(&document, &typst_pdf::PdfOptions::default())
-            .map_err(|e| std::io::Error::other(format!("{:?}", e)))
+        Ok(world)
+    }
+
+    /// Render just the packet's preamble to HTML, e.g., for a landing page
+    ///
+    /// Returns `None` if the packet has no preamble.
+    pub fn render_preamble_html(&self) -> Result<Option<String>, render::markdown::RenderError> {
+        self.packet
+            .preamble
+            .as_deref()
+            .map(render::markdown::MarkdownRenderable::html)
+            .transpose()
+    }
+
+    /// Render just the packet's preamble to typst content, e.g., for a cover page
+    ///
+    /// Returns `None` if the packet has no preamble.
+    pub fn render_preamble_content(
+        &self,
+        world: &impl typst::World,
+    ) -> Result<Option<typst::foundations::Content>, render::markdown::RenderError> {
+        self.packet
+            .preamble
+            .as_deref()
+            .map(|p| p.content(world))
+            .transpose()
+    }
+
+    /// Render the packet to HTML, with its stylesheet kept separate
+    ///
+    /// The problems are laid out like the default PDF template: each problem's title and
+    /// description, followed by its visible tests.
+    pub fn render_html(&self) -> Result<render::html::HtmlPacket, render::markdown::RenderError> {
+        render::html::render_packet(&self.packet)
+    }
+
+    /// Render the packet to a PDF, replacing the contents of `buf`
+    ///
+    /// This is the allocation-conscious way to render repeatedly (e.g., in a server loop), since
+    /// `buf` keeps its capacity between renders.  `typst-pdf` still builds its own vector, but it
+    /// is dropped as soon as it is copied into `buf`.
+    pub fn render_pdf_into(
+        &self,
+        buf: &mut Vec<u8>,
+        template: Option<String>,
+    ) -> Result<(), RenderPdfError> {
+        let pdf = self.render_pdf(template)?;
+        buf.clear();
+        buf.extend_from_slice(&pdf);
+        Ok(())
     }
 
     /// Note: In the current implementation of `typst-pdf`, this just renders to a vector and then
     /// writes that to the `writer`.
-    pub fn write_pdf<W>(&self, writer: &mut W, template: Option<String>) -> std::io::Result<()>
+    pub fn write_pdf<W>(
+        &self,
+        writer: &mut W,
+        template: Option<String>,
+    ) -> Result<(), RenderPdfError>
     where
         W: std::io::Write,
     {
         // XXX: I would really love it if typst offered an API that did not have to create a vec
         // just to render the PDF
         let vec = self.render_pdf(template)?;
-        writer.write_all(&vec)
+        Ok(writer.write_all(&vec)?)
+    }
+
+    /// Render the packet to a PDF without blocking the async runtime
+    ///
+    /// Compilation is CPU-heavy, so it is run on tokio's blocking thread pool.  See
+    /// [`Config::render_pdf`] for details on the template.
+    #[cfg(feature = "tokio")]
+    pub async fn render_pdf_async(
+        &self,
+        template: Option<String>,
+    ) -> Result<Vec<u8>, RenderPdfError> {
+        let config = self.clone();
+        tokio::task::spawn_blocking(move || config.render_pdf(template))
+            .await
+            .map_err(std::io::Error::other)?
     }
 }
 
@@ -384,12 +1317,16 @@ impl Default for Config {
     fn default() -> Self {
         Self {
             hash: 3141592653589793238,
+            dir: PathBuf::new(),
             setup: None,
             port: default_port(),
             languages: Default::default(),
             accounts: Default::default(),
             packet: Default::default(),
             test_runner: Default::default(),
+            render: Default::default(),
+            start_time: None,
+            end_time: None,
         }
     }
 }
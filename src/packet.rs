@@ -1,11 +1,13 @@
-use std::collections::HashSet;
+use std::{
+    collections::BTreeSet,
+    path::{Path, PathBuf},
+};
 
 use serde::{Deserialize, Serialize};
 
-use crate::{
-    render::markdown::{MarkdownRenderable, RenderError},
-    roi, RawOrImport,
-};
+#[cfg(feature = "render")]
+use crate::render::markdown::{MarkdownOptions, RenderError};
+use crate::{render::markdown::MarkdownRenderable, roi, RawOrImport, TestRunner};
 
 /// Structure represnting data for a problem
 #[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq, Default)]
@@ -14,19 +16,95 @@ pub struct Problem {
     /// The languages that may be used to solve this question
     ///
     /// Must be a subset of the languages listed in the Config
-    pub languages: Option<HashSet<String>>,
+    pub languages: Option<BTreeSet<String>>,
     /// The title for this specific problem
     pub title: String,
     /// The description of this problem (supports markdown)
     pub description: Option<RawOrImport<MarkdownRenderable, roi::Raw>>,
     /// The tests that will be used on this problem
     pub tests: Vec<Test>,
+    /// How difficult this problem is, where lower is easier
+    ///
+    /// Used by [`Packet::sorted_by_difficulty`]
+    pub difficulty: Option<u8>,
+    /// Where this problem goes in the packet, when [`Packet::sort_by_order`] is set, where lower
+    /// comes first
+    ///
+    /// This lets imported problems control their position regardless of where they are listed.
+    pub order: Option<i32>,
+    /// Typst template used to render this problem instead of the packet's template
+    ///
+    /// The template has `#problem` defined with the same fields as each item in `#problems`, and
+    /// its output is available to the packet's template as the problem's `body`.
+    ///
+    /// Relative to the directory containing the config file, see
+    /// [`Config::resolve_path`](crate::Config::resolve_path)
+    pub template: Option<PathBuf>,
+    /// Tags describing this problem (e.g., `graphs` or `dp`), which templates may use to group
+    /// or label problems
+    #[serde(default)]
+    pub tags: Vec<String>,
+    /// A reference solution to this problem, for the use of admins
+    ///
+    /// This is never shown to competitors.
+    pub solution: Option<RawOrImport<String, roi::Raw>>,
+    /// The language in which [`Problem::solution`] is written
+    pub solution_language: Option<String>,
+    /// A program which judges a submission's output, instead of comparing it to the expected
+    /// output
+    ///
+    /// This is never shown to competitors.
+    pub checker: Option<RawOrImport<String, roi::Raw>>,
+    /// The language in which [`Problem::checker`] is written
+    ///
+    /// This must be one of the languages in the config if there is a checker.
+    pub checker_language: Option<String>,
+}
+
+impl Problem {
+    /// The tests which are shown to competitors
+    pub fn visible_tests(&self) -> impl Iterator<Item = &Test> {
+        self.tests.iter().filter(|t| t.visible)
+    }
+
+    /// The test which is shown to competitors as an example, which is the first visible test
+    pub fn example_test(&self) -> Option<&Test> {
+        self.visible_tests().next()
+    }
+
+    /// The description of this problem as plain text, see [`MarkdownRenderable::plain_text`]
+    pub fn description_plain(&self) -> Option<String> {
+        self.description
+            .as_deref()
+            .map(MarkdownRenderable::plain_text)
+    }
+
+    /// Whether the title or description of this problem contains `query`, ignoring case
+    pub fn matches_search(&self, query: &str) -> bool {
+        let query = query.to_lowercase();
+        self.title.to_lowercase().contains(&query)
+            || self
+                .description_plain()
+                .is_some_and(|d| d.to_lowercase().contains(&query))
+    }
+
+    /// The number of tests for this problem, including hidden tests
+    pub fn test_count(&self) -> usize {
+        self.tests.len()
+    }
+
+    /// The number of tests for this problem which are shown to competitors
+    pub fn visible_test_count(&self) -> usize {
+        self.visible_tests().count()
+    }
 }
 
+#[cfg(feature = "render")]
 impl Problem {
     pub(crate) fn as_value(
         &self,
         world: &impl typst::World,
+        options: MarkdownOptions,
     ) -> Result<typst::foundations::Value, RenderError> {
         use crate::util;
         use typst::foundations::Value;
@@ -40,20 +118,84 @@ impl Problem {
         dict.insert("title".into(), util::convert(&self.title));
 
         if let Some(desc) = &self.description {
-            dict.insert("description".into(), Value::Content(desc.content(world)?));
+            dict.insert(
+                "description".into(),
+                Value::Content(desc.content_with(world, options)?),
+            );
         }
 
         dict.insert("tests".into(), util::convert(&self.tests));
 
+        if let Some(example) = self.example_test() {
+            // without trailing newlines, so that it can be shown as a tidy block
+            let mut ex = typst::foundations::Dict::new();
+            ex.insert("input".into(), Value::Str(example.input.trim_end().into()));
+            ex.insert(
+                "output".into(),
+                Value::Str(example.output.trim_end().into()),
+            );
+            dict.insert("example".into(), Value::Dict(ex));
+        }
+
+        dict.insert("tags".into(), util::convert(&self.tags));
+
+        if let Some(difficulty) = self.difficulty {
+            dict.insert("difficulty".into(), util::convert(&difficulty));
+        }
+
         Ok(Value::Dict(dict))
     }
+
+    /// Render [`Problem::template`], if there is one, with `#problem` defined as `value`
+    ///
+    /// Relative template paths are resolved against `dir`, the directory containing the config.
+    pub(crate) fn render_template(
+        &self,
+        world: &impl typst::World,
+        value: &typst::foundations::Value,
+        dir: &std::path::Path,
+    ) -> std::io::Result<Option<typst::foundations::Content>> {
+        use comemo::Track;
+        use typst::foundations::{Scope, Value};
+
+        let Some(path) = &self.template else {
+            return Ok(None);
+        };
+        let path = dir.join(path);
+        let source = std::fs::read_to_string(&path).map_err(|e| {
+            std::io::Error::new(
+                e.kind(),
+                format!("Failed to read template {}: {}", path.display(), e),
+            )
+        })?;
+
+        let mut scope = Scope::new();
+        scope.define("problem", value.clone());
+        let world: &dyn typst::World = world;
+        let content = typst::eval::eval_string(
+            world.track(),
+            &source,
+            typst::syntax::Span::detached(),
+            typst::eval::EvalMode::Markup,
+            scope,
+        )
+        .map_err(RenderError::from)?;
+
+        match content {
+            Value::Content(content) => Ok(Some(content)),
+            _ => unreachable!("markup always evaluates to content"),
+        }
+    }
 }
 
 /// A specific test that will be used to validate that user's code.
 ///
 /// The input and expected output for visible tests will be shown to the user
+///
+/// The input and output may either be given inline or read from a file with `input_file` and
+/// `output_file`.
 #[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq, Ord, PartialOrd, Hash, Default)]
-#[serde(deny_unknown_fields)]
+#[serde(try_from = "TomlTest")]
 pub struct Test {
     /// The input that will be provided via STDIN to the test
     pub input: String,
@@ -64,6 +206,131 @@ pub struct Test {
     /// The first visible test will be shown as an example for the user
     #[serde(default = "crate::default_false")]
     pub visible: bool,
+    /// The file from which [`Test::input`] was read, if it was not given inline
+    ///
+    /// In the config this is relative to the file in which the test is written, but this is the
+    /// resolved path, see [`Config::resolve_path`](crate::Config::resolve_path)
+    #[serde(skip)]
+    pub input_file: Option<PathBuf>,
+    /// The file from which [`Test::output`] was read, if it was not given inline
+    ///
+    /// Resolved in the same way as [`Test::input_file`]
+    #[serde(skip)]
+    pub output_file: Option<PathBuf>,
+    /// The expected output from STDERR, if it should be checked
+    pub expected_stderr: Option<String>,
+    /// The expected exit code, if it should be checked
+    pub expected_exit_code: Option<i32>,
+}
+
+/// What a submission produced when it was run on a [`Test`], see [`Test::matches`]
+///
+/// A plain string converts into this as the STDOUT of a run which wrote nothing to STDERR and
+/// exited successfully.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, Default)]
+pub struct ActualOutput<'a> {
+    /// What was written to STDOUT
+    pub stdout: &'a str,
+    /// What was written to STDERR
+    pub stderr: &'a str,
+    /// The exit code of the process
+    pub exit_code: i32,
+}
+
+impl<'a> From<&'a str> for ActualOutput<'a> {
+    fn from(stdout: &'a str) -> Self {
+        Self {
+            stdout,
+            ..Default::default()
+        }
+    }
+}
+
+impl Test {
+    /// Whether `actual` is the expected output of this test, using the comparison settings of
+    /// `runner`
+    ///
+    /// STDOUT is always compared, while STDERR and the exit code are only compared if
+    /// [`Test::expected_stderr`] or [`Test::expected_exit_code`] are given.
+    ///
+    /// If [`TestRunner::normalize_line_endings`] is true, `\r\n` is treated as `\n`.  Then, both
+    /// outputs are transformed according to [`TestRunner::comparison`], e.g., to ignore leading
+    /// and trailing whitespace.  Finally, if [`TestRunner::case_insensitive`] is true, both
+    /// outputs are lowercased.
+    pub fn matches<'a>(&self, actual: impl Into<ActualOutput<'a>>, runner: &TestRunner) -> bool {
+        let actual = actual.into();
+        let normalize = |s: &str| -> String {
+            let s = if runner.normalize_line_endings {
+                s.replace("\r\n", "\n")
+            } else {
+                s.to_string()
+            };
+            let s = runner.comparison.apply(&s);
+            if runner.case_insensitive {
+                s.to_lowercase()
+            } else {
+                s
+            }
+        };
+        let stderr_matches = match &self.expected_stderr {
+            Some(stderr) => normalize(stderr) == normalize(actual.stderr),
+            None => true,
+        };
+        let exit_code_matches = match self.expected_exit_code {
+            Some(code) => code == actual.exit_code,
+            None => true,
+        };
+        normalize(&self.output) == normalize(actual.stdout) && stderr_matches && exit_code_matches
+    }
+}
+
+/// Test as represented in the toml file
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct TomlTest {
+    input: Option<String>,
+    input_file: Option<PathBuf>,
+    output: Option<String>,
+    output_file: Option<PathBuf>,
+    #[serde(default = "crate::default_false")]
+    visible: bool,
+    expected_stderr: Option<String>,
+    expected_exit_code: Option<i32>,
+}
+
+impl TomlTest {
+    /// Get the inline value or read it from `file`, which has already been resolved with
+    /// [`roi::relative_to_file`]
+    fn resolve(name: &str, inline: Option<String>, file: Option<&Path>) -> Result<String, String> {
+        match (inline, file) {
+            (Some(inline), None) => Ok(inline),
+            (None, Some(file)) => std::fs::read_to_string(file)
+                .map_err(|e| format!("Failed to read {}: {}", file.display(), e)),
+            (Some(_), Some(_)) => Err(format!(
+                "only one of `{0}` and `{0}_file` may be specified",
+                name
+            )),
+            (None, None) => Err(format!("missing field `{0}` or `{0}_file`", name)),
+        }
+    }
+}
+
+impl TryFrom<TomlTest> for Test {
+    type Error = String;
+
+    fn try_from(value: TomlTest) -> Result<Self, Self::Error> {
+        let input_file = value.input_file.as_deref().map(roi::relative_to_file);
+        let output_file = value.output_file.as_deref().map(roi::relative_to_file);
+        Ok(Self {
+            input: TomlTest::resolve("input", value.input, input_file.as_deref())?,
+            output: TomlTest::resolve("output", value.output, output_file.as_deref())?,
+            visible: value.visible,
+            input_file,
+            output_file,
+            expected_stderr: value.expected_stderr,
+            expected_exit_code: value.expected_exit_code,
+        })
+    }
 }
 
 /// A packet which contains configuration for problems and their tests
@@ -72,8 +339,92 @@ pub struct Test {
 pub struct Packet {
     /// Title of the packet
     pub title: String,
+    /// Author of the packet, which is included in the PDF metadata
+    pub author: Option<String>,
+    /// Keywords describing the packet, which are included in the PDF metadata
+    #[serde(default)]
+    pub keywords: Vec<String>,
     /// Information about the packet that will be included at the top of the file
     pub preamble: Option<RawOrImport<MarkdownRenderable, roi::Raw>>,
     /// The list of problems for this
+    ///
+    /// Each problem may be imported from its own file, or a file containing a list of problems
+    /// (as `[[problems]]`) may be imported to add all of them.
+    #[serde(deserialize_with = "deserialize_problems")]
     pub problems: Vec<RawOrImport<Problem>>,
+    /// Whether the problems are rendered in the order given by their [`Problem::order`], rather
+    /// than the order in which they are listed
+    #[serde(default = "crate::default_false")]
+    pub sort_by_order: bool,
+}
+
+fn deserialize_problems<'de, D>(de: D) -> Result<Vec<RawOrImport<Problem>>, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    roi::deserialize_flattened(de, "problems")
+}
+
+impl Packet {
+    /// Iterate over the problems in this packet, in order
+    pub fn iter_problems(&self) -> impl Iterator<Item = &Problem> {
+        self.problems.iter().map(|p| &**p)
+    }
+
+    /// Get the problem at index `i`
+    pub fn problem(&self, i: usize) -> Option<&Problem> {
+        self.problems.get(i).map(|p| &**p)
+    }
+
+    /// The number of problems in this packet
+    pub fn problem_count(&self) -> usize {
+        self.problems.len()
+    }
+
+    /// The problems whose title or description contains `query`, ignoring case, in order
+    pub fn search(&self, query: &str) -> Vec<&Problem> {
+        self.iter_problems()
+            .filter(|p| p.matches_search(query))
+            .collect()
+    }
+
+    /// The number of tests across every problem in this packet, including hidden tests
+    pub fn test_count(&self) -> usize {
+        self.iter_problems().map(Problem::test_count).sum()
+    }
+
+    /// The number of tests across every problem in this packet which are shown to competitors
+    pub fn visible_test_count(&self) -> usize {
+        self.iter_problems().map(Problem::visible_test_count).sum()
+    }
+
+    /// Every tag used by any problem in this packet
+    pub fn tags(&self) -> BTreeSet<String> {
+        self.iter_problems()
+            .flat_map(|p| p.tags.iter().cloned())
+            .collect()
+    }
+
+    /// The problems in this packet ordered from easiest to hardest
+    ///
+    /// Problems without a difficulty come last, and problems with equal difficulty stay in the
+    /// order in which they appear in the packet.
+    pub fn sorted_by_difficulty(&self) -> Vec<&Problem> {
+        let mut problems = self.iter_problems().collect::<Vec<_>>();
+        problems.sort_by_key(|p| (p.difficulty.is_none(), p.difficulty));
+        problems
+    }
+
+    /// The problems in the order in which they are rendered
+    ///
+    /// If [`Packet::sort_by_order`] is set, this is ordered by [`Problem::order`], with problems
+    /// without an order coming last and ties staying in the order in which they are listed.
+    /// Otherwise, this is the order in which they are listed.
+    pub fn ordered_problems(&self) -> Vec<&Problem> {
+        let mut problems = self.iter_problems().collect::<Vec<_>>();
+        if self.sort_by_order {
+            problems.sort_by_key(|p| (p.order.is_none(), p.order));
+        }
+        problems
+    }
 }
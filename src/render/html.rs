@@ -0,0 +1,87 @@
+//! Rendering a whole packet to HTML, e.g., to show it on a web page rather than as a PDF
+
+use pulldown_cmark::{CodeBlockKind, CowStr, Event, HeadingLevel, Tag, TagEnd};
+
+use super::markdown::RenderError;
+use crate::packet::Packet;
+
+/// The stylesheet for [`HtmlPacket::html`]
+const PACKET_CSS: &str = include_str!("../../data/packet.css");
+
+/// A packet rendered to HTML, see [`Config::render_html`](crate::Config::render_html)
+///
+/// The stylesheet is kept separate from the body so that it can be served (and cached) on its
+/// own, e.g., with a `<link rel="stylesheet">` in the page that includes the body.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct HtmlPacket {
+    /// The body of the packet, wrapped in a `<div class="packet">`
+    pub html: String,
+    /// The stylesheet for the classes used in [`HtmlPacket::html`]
+    pub css: String,
+}
+
+fn heading(level: HeadingLevel, class: &'static str, text: &str) -> [Event<'static>; 3] {
+    [
+        Event::Start(Tag::Heading {
+            level,
+            id: None,
+            classes: vec![class.into()],
+            attrs: vec![],
+        }),
+        Event::Text(CowStr::from(text.to_string())),
+        Event::End(TagEnd::Heading(level)),
+    ]
+}
+
+fn code(class: &'static str, label: &'static str, text: &str) -> Vec<Event<'static>> {
+    vec![
+        Event::Html(format!("<div class=\"{}\">\n<strong>{}</strong>\n", class, label).into()),
+        Event::Start(Tag::CodeBlock(CodeBlockKind::Fenced("".into()))),
+        Event::Text(CowStr::from(text.to_string())),
+        Event::End(TagEnd::CodeBlock),
+        Event::Html("</div>\n".into()),
+    ]
+}
+
+/// Renders the packet to HTML, laid out like the default PDF template
+pub(crate) fn render_packet(packet: &Packet) -> Result<HtmlPacket, RenderError> {
+    let mut events = vec![Event::Html("<div class=\"packet\">\n".into())];
+    events.extend(heading(HeadingLevel::H1, "packet-title", &packet.title));
+    if let Some(preamble) = &packet.preamble {
+        events.push(Event::Html(
+            format!(
+                "<section class=\"preamble\">\n{}</section>\n",
+                preamble.html()?
+            )
+            .into(),
+        ));
+    }
+
+    for problem in packet.ordered_problems() {
+        events.push(Event::Html("<section class=\"problem\">\n".into()));
+        events.extend(heading(HeadingLevel::H2, "problem-title", &problem.title));
+        if let Some(description) = &problem.description {
+            events.push(Event::Html(description.html()?.into()));
+        }
+        for (i, test) in problem.visible_tests().enumerate() {
+            let title = format!("Test case {}", i + 1);
+            events.extend(heading(HeadingLevel::H3, "test-case-title", &title));
+            events.push(Event::Html("<div class=\"test-case\">\n".into()));
+            // like the template, tests with only an output don't show the empty input
+            if !test.input.is_empty() || test.output.is_empty() {
+                events.extend(code("test-input", "Input", &test.input));
+            }
+            events.extend(code("test-output", "Output", &test.output));
+            events.push(Event::Html("</div>\n".into()));
+        }
+        events.push(Event::Html("</section>\n".into()));
+    }
+    events.push(Event::Html("</div>\n".into()));
+
+    let mut html = String::new();
+    pulldown_cmark::html::push_html(&mut html, events.into_iter());
+    Ok(HtmlPacket {
+        html,
+        css: PACKET_CSS.into(),
+    })
+}
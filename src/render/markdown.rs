@@ -1,42 +1,216 @@
-use std::{num::NonZero, str::FromStr};
+use std::str::FromStr;
+#[cfg(feature = "render")]
+use std::{
+    collections::HashSet,
+    num::NonZero,
+    ops::Range,
+    path::{Path, PathBuf},
+};
 
+#[cfg(feature = "render")]
+use base64::prelude::{Engine as _, BASE64_STANDARD};
+#[cfg(feature = "render")]
 use comemo::Track;
+#[cfg(feature = "render")]
 use ecow::EcoVec;
-use pulldown_cmark::{Alignment, CodeBlockKind, Event, Options, Parser, Tag};
-use pulldown_cmark_ast::{Ast, Tree};
+#[cfg(feature = "render")]
+use pulldown_cmark::{Alignment, BlockQuoteKind, CodeBlockKind, Tag, TagEnd};
+use pulldown_cmark::{Event, Options, Parser};
+#[cfg(feature = "render")]
+use pulldown_cmark_ast::{Ast, Spanned, Tree};
 use serde::{Deserialize, Serialize};
+#[cfg(feature = "render")]
 use typst::{
     diag::{EcoString, SourceDiagnostic},
     foundations::{Content, Packed, Scope, Smart, Value},
-    layout::{Celled, Length, Ratio, Sizing, TrackSizings},
+    layout::{
+        Abs, BlockBody, BlockElem, Celled, Em, Fr, Length, Ratio, Sides, Sizing, TrackSizings,
+    },
     model::{
         EnumElem, EnumItem, FigureElem, HeadingElem, LinkElem, LinkTarget, ListElem, ListItem,
-        ParbreakElem, TableCell, TableChild, TableElem, TableHeader, TableItem, Url,
+        ParbreakElem, TableCell, TableChild, TableElem, TableHeader, TableItem, TermItem,
+        TermsElem, Url,
     },
     syntax::Span,
     text::{LinebreakElem, RawContent, RawElem, SpaceElem, StrikeElem, TextElem},
-    visualize::LineElem,
+    visualize::{Color, LineElem, Stroke},
     World,
 };
 
+#[cfg(feature = "render")]
 use crate::render::typst::TypstWrapperWorld;
 
+#[cfg(feature = "render")]
 #[derive(thiserror::Error, Debug, PartialEq, Eq)]
 pub enum RenderError {
     #[error("Error while processing typst: {0:?}")]
     TypstError(Vec<SourceDiagnostic>),
-    #[error("HTML tags are unsupported in Markdown")]
-    UnsupportedHtml,
+    /// A maths block failed to compile
+    ///
+    /// `span` is the byte range of the maths block within the markdown source.
+    #[error("Error while processing maths at {span:?}: {diagnostics:?}")]
+    MathError {
+        span: Range<usize>,
+        diagnostics: Vec<SourceDiagnostic>,
+    },
+    /// The markdown contains raw HTML, which cannot be rendered with typst
+    ///
+    /// `span` is the byte range of the HTML within the markdown source.  See [`HtmlMode`].
+    #[error("HTML tags are unsupported in Markdown (at {span:?})")]
+    UnsupportedHtml { span: Range<usize> },
+    /// The markdown contains an image, which cannot be rendered with typst
+    ///
+    /// `span` is the byte range of the image within the markdown source.  Images are only
+    /// supported in HTML, see [`MarkdownOptions::image_dir`].
+    #[error("Images are unsupported in Markdown rendered to a PDF (at {span:?})")]
+    UnsupportedImage { span: Range<usize> },
+    /// A maths block is longer than [`MathLimits::max_length`], so it was not compiled
+    #[error("Maths at {span:?} is {length} bytes long, but at most {limit} bytes are allowed")]
+    MathTooLong {
+        span: Range<usize>,
+        length: usize,
+        limit: usize,
+    },
+    /// There are more maths blocks than [`MathLimits::max_count`], so the rest were not compiled
+    ///
+    /// `span` is the byte range of the first maths block over the limit.
+    #[error("Too many maths blocks (at {span:?}): at most {limit} are allowed")]
+    TooMuchMath { span: Range<usize>, limit: usize },
+}
+
+#[cfg(feature = "render")]
+impl RenderError {
+    /// The byte range within the markdown source that caused this error, if known
+    pub fn span(&self) -> Option<Range<usize>> {
+        match self {
+            RenderError::MathError { span, .. }
+            | RenderError::UnsupportedHtml { span }
+            | RenderError::UnsupportedImage { span }
+            | RenderError::MathTooLong { span, .. }
+            | RenderError::TooMuchMath { span, .. } => Some(span.clone()),
+            RenderError::TypstError(_) => None,
+        }
+    }
 }
 
+#[cfg(feature = "render")]
 type RenderResult<T> = Result<T, RenderError>;
 
+/// Something in markdown which can be rendered but probably isn't what the author meant, see
+/// [`MarkdownRenderable::html_strict`]
+#[cfg(feature = "render")]
+#[derive(thiserror::Error, Debug, Clone, PartialEq, Eq, Hash)]
+pub enum MarkdownWarning {
+    /// A fenced code block's language is not known, so it is not highlighted
+    ///
+    /// `span` is the byte range of the code block within the markdown source.
+    #[error("Unknown code block language '{language}' (at {span:?}), so it is not highlighted")]
+    UnknownCodeLanguage {
+        span: Range<usize>,
+        language: String,
+    },
+}
+
+/// Limits on the maths in markdown which is rendered to HTML, so that rendering untrusted
+/// markdown cannot take too long
+///
+/// The limits are checked before any maths is compiled.
+#[cfg(feature = "render")]
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub struct MathLimits {
+    /// The longest a single maths block may be, in bytes
+    pub max_length: usize,
+    /// The most maths blocks that the markdown may contain
+    pub max_count: usize,
+}
+
+#[cfg(feature = "render")]
+impl MathLimits {
+    /// Check that the `count`th maths block in the markdown, `math`, is within these limits
+    fn check(&self, math: &str, count: usize, span: &Range<usize>) -> RenderResult<()> {
+        if count > self.max_count {
+            Err(RenderError::TooMuchMath {
+                span: span.clone(),
+                limit: self.max_count,
+            })
+        } else if math.len() > self.max_length {
+            Err(RenderError::MathTooLong {
+                span: span.clone(),
+                length: math.len(),
+                limit: self.max_length,
+            })
+        } else {
+            Ok(())
+        }
+    }
+}
+
+#[cfg(feature = "render")]
+impl Default for MathLimits {
+    fn default() -> Self {
+        Self {
+            max_length: 4096,
+            max_count: 1000,
+        }
+    }
+}
+
+/// What to do with raw HTML when rendering markdown to typst content
+#[cfg(feature = "render")]
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, Default)]
+pub enum HtmlMode {
+    /// Fail with [`RenderError::UnsupportedHtml`]
+    #[default]
+    Error,
+    /// Leave the HTML out of the rendered content
+    Strip,
+}
+
+/// How the columns of markdown tables are sized when rendering to typst content
+#[cfg(feature = "render")]
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, Default)]
+pub enum TableWidths {
+    /// Each column is as wide as its content, so wide tables may overflow the page
+    #[default]
+    Auto,
+    /// The columns share the width of the page, in proportion to the length of their content
+    Fit,
+}
+
+/// Options for rendering markdown into typst content
+#[cfg(feature = "render")]
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Default)]
+pub struct MarkdownOptions {
+    /// What to do with raw HTML
+    pub html: HtmlMode,
+    /// How the columns of tables are sized
+    pub tables: TableWidths,
+    /// The directory from which local images are embedded when rendering to HTML (see
+    /// [`MarkdownRenderable::html_with_options`])
+    ///
+    /// Only relative paths which stay within this directory are embedded.  If this is `None`,
+    /// no local files are read, and local images are replaced with their alt text.
+    pub image_dir: Option<PathBuf>,
+}
+
+#[cfg(feature = "render")]
+impl From<HtmlMode> for MarkdownOptions {
+    fn from(html: HtmlMode) -> Self {
+        Self {
+            html,
+            ..Default::default()
+        }
+    }
+}
+
+#[cfg(feature = "render")]
 impl From<EcoVec<SourceDiagnostic>> for RenderError {
     fn from(value: EcoVec<SourceDiagnostic>) -> Self {
         Self::TypstError(value.to_vec())
     }
 }
 
+#[cfg(feature = "render")]
 impl From<RenderError> for std::io::Error {
     fn from(val: RenderError) -> Self {
         std::io::Error::other(format!("{}", val))
@@ -48,9 +222,18 @@ const CMARK_OPTIONS: Options = Options::from_bits_truncate(
     (1 << 1) // Options::ENABLE_TABLES
     | (1 << 5) // Options::ENABLE_SMART_PUNCTUATION
     | (1 << 3) // Options::ENABLE_STRIKETHROUGH
-    | (1 << 10), // Options::ENABLE_MATH
+    | (1 << 10) // Options::ENABLE_MATH
+    | (1 << 11), // Options::ENABLE_GFM
 );
 
+/// Markdown which can be rendered to HTML or typst content
+///
+/// Along with CommonMark, this supports tables, strikethrough, `$maths$` (written in typst's
+/// maths syntax), and GitHub-style `> [!NOTE]` block quotes.
+///
+/// A paragraph in which every line is of the form `term :: description` is rendered as a
+/// description list.  The ` :: ` must be surrounded by spaces and must not be inside any
+/// formatting.
 #[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq, Ord, PartialOrd, Hash, Default)]
 #[repr(transparent)]
 #[serde(transparent)]
@@ -85,63 +268,285 @@ impl MarkdownRenderable {
         &self.0
     }
 
+    /// The text of this markdown without any formatting, e.g., for search indexing
+    ///
+    /// Maths and code are included as they were written, and each block (paragraph, heading,
+    /// list item, etc.) is on its own line.
+    pub fn plain_text(&self) -> String {
+        let mut text = String::new();
+        for event in Parser::new_ext(self.raw(), CMARK_OPTIONS) {
+            match event {
+                Event::Text(s) | Event::Code(s) | Event::InlineMath(s) | Event::DisplayMath(s) => {
+                    text.push_str(&s)
+                }
+                Event::SoftBreak => text.push(' '),
+                Event::HardBreak | Event::Rule => text.push('\n'),
+                Event::End(pulldown_cmark::TagEnd::TableCell) => text.push(' '),
+                Event::End(
+                    pulldown_cmark::TagEnd::Paragraph
+                    | pulldown_cmark::TagEnd::Heading(_)
+                    | pulldown_cmark::TagEnd::Item
+                    | pulldown_cmark::TagEnd::CodeBlock
+                    | pulldown_cmark::TagEnd::TableHead
+                    | pulldown_cmark::TagEnd::TableRow,
+                ) if !text.ends_with('\n') => text.push('\n'),
+                _ => {}
+            }
+        }
+        text.truncate(text.trim_end().len());
+        text
+    }
+
+    /// The number of words of prose in this markdown, not counting code or maths
+    pub fn word_count(&self) -> usize {
+        let mut prose = String::new();
+        let mut in_code_block = false;
+        for event in Parser::new_ext(self.raw(), CMARK_OPTIONS) {
+            match event {
+                Event::Start(pulldown_cmark::Tag::CodeBlock(_)) => in_code_block = true,
+                Event::End(pulldown_cmark::TagEnd::CodeBlock) => in_code_block = false,
+                Event::Text(s) if !in_code_block => prose.push_str(&s),
+                // text either side of these is in separate words, but text split by formatting
+                // (e.g., `bin*ary*`) is not
+                Event::Code(_)
+                | Event::InlineMath(_)
+                | Event::DisplayMath(_)
+                | Event::SoftBreak
+                | Event::HardBreak
+                | Event::End(
+                    pulldown_cmark::TagEnd::Paragraph
+                    | pulldown_cmark::TagEnd::Heading(_)
+                    | pulldown_cmark::TagEnd::Item
+                    | pulldown_cmark::TagEnd::TableCell,
+                ) => prose.push(' '),
+                _ => {}
+            }
+        }
+        // punctuation left over from around code or maths is not a word
+        prose
+            .split_whitespace()
+            .filter(|w| w.chars().any(char::is_alphanumeric))
+            .count()
+    }
+
+    /// Roughly how long it takes to read this markdown, at [`WORDS_PER_MINUTE`]
+    ///
+    /// Like [`MarkdownRenderable::word_count`], this does not count code or maths, so it is
+    /// an underestimate for descriptions which have a lot of them.
+    pub fn reading_time(&self) -> std::time::Duration {
+        std::time::Duration::from_secs_f64(self.word_count() as f64 * 60.0 / WORDS_PER_MINUTE)
+    }
+
+    /// Every maths block in this markdown, in order, without compiling them
+    pub fn math_expressions(&self) -> Vec<MathExpr> {
+        Parser::new_ext(self.raw(), CMARK_OPTIONS)
+            .filter_map(|event| match event {
+                Event::InlineMath(math) => Some(MathExpr {
+                    source: math.into_string(),
+                    display: false,
+                }),
+                Event::DisplayMath(math) => Some(MathExpr {
+                    source: math.into_string(),
+                    display: true,
+                }),
+                _ => None,
+            })
+            .collect()
+    }
+}
+
+/// The reading speed used by [`MarkdownRenderable::reading_time`]
+pub const WORDS_PER_MINUTE: f64 = 200.0;
+
+/// A maths block in markdown, see [`MarkdownRenderable::math_expressions`]
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct MathExpr {
+    /// The typst maths, without the surrounding `$`s
+    pub source: String,
+    /// Whether this is a display block (`$$...$$`) rather than inline maths
+    pub display: bool,
+}
+
+#[cfg(feature = "render")]
+impl MarkdownRenderable {
     /// Renders the given string into HTML
     ///
-    /// This uses typst to fill in the maths blocks.
+    /// This uses typst to fill in the maths blocks, within the default [`MathLimits`].  Each
+    /// heading is given an `id` (e.g., `## Sample Input` has the id `sample-input`), so that it
+    /// can be linked to.
     pub fn html(&self) -> RenderResult<String> {
-        let parser = Parser::new_ext(self.raw(), CMARK_OPTIONS);
+        self.html_with(&MathLimits::default())
+    }
+
+    /// Renders the given string into HTML, like [`MarkdownRenderable::html`], with the given
+    /// limits on the maths
+    pub fn html_with(&self, limits: &MathLimits) -> RenderResult<String> {
+        self.html_and_warnings(limits, &MarkdownOptions::default())
+            .map(|(html, _)| html)
+    }
+
+    /// Renders the given string into HTML, like [`MarkdownRenderable::html`], embedding local
+    /// images from [`MarkdownOptions::image_dir`]
+    pub fn html_with_options(&self, options: &MarkdownOptions) -> RenderResult<String> {
+        self.html_and_warnings(&MathLimits::default(), options)
+            .map(|(html, _)| html)
+    }
+
+    /// Renders the given string into HTML, like [`MarkdownRenderable::html`], along with
+    /// warnings for anything that is rendered but probably isn't what the author meant
+    ///
+    /// For example, a code block in ` ```pyton ` is rendered as plain text, which
+    /// [`MarkdownRenderable::html`] does silently.
+    pub fn html_strict(&self) -> RenderResult<(String, Vec<MarkdownWarning>)> {
+        self.html_and_warnings(&MathLimits::default(), &MarkdownOptions::default())
+    }
+
+    fn html_and_warnings(
+        &self,
+        limits: &MathLimits,
+        options: &MarkdownOptions,
+    ) -> RenderResult<(String, Vec<MarkdownWarning>)> {
+        // check all of the maths before compiling any of it, so that the work done on hostile
+        // input is bounded
+        Parser::new_ext(self.raw(), CMARK_OPTIONS)
+            .into_offset_iter()
+            .filter_map(|(event, span)| match event {
+                Event::InlineMath(math) | Event::DisplayMath(math) => Some((math, span)),
+                _ => None,
+            })
+            .enumerate()
+            .try_for_each(|(i, (math, span))| limits.check(&math, i + 1, &span))?;
+
+        let mut warnings = Vec::new();
         let mut errors = Vec::new();
-        let parser = parser.map(|event| match event {
-            pulldown_cmark::Event::InlineMath(cow_str) => {
-                // TODO: This should parse the cow_str into a Content and somehow convert that to a
-                // page.
-                let f = format!(
-                    "#set page(width: auto, height: auto, margin: 0em)
+        let parser = Parser::new_ext(self.raw(), CMARK_OPTIONS)
+            .into_offset_iter()
+            .map(|(event, span)| match event {
+                pulldown_cmark::Event::InlineMath(cow_str) => {
+                    // TODO: This should parse the cow_str into a Content and somehow convert that to a
+                    // page.
+                    let f = format!(
+                        "#set page(width: auto, height: auto, margin: 0em)
                     ${}$",
-                    cow_str
-                );
-                let world = TypstWrapperWorld::new(f);
-                match typst::compile(&world).output {
-                    Ok(doc) => {
-                        let svg = typst_svg::svg(&doc.pages[0]);
-                        Event::InlineHtml(svg.into())
-                    }
-                    Err(err) => {
-                        errors.extend(err);
-                        Event::Text("".into())
+                        cow_str
+                    );
+                    let world = TypstWrapperWorld::new(f);
+                    match typst::compile(&world).output {
+                        Ok(doc) => {
+                            let svg = typst_svg::svg(&doc.pages[0]);
+                            Event::InlineHtml(svg.into())
+                        }
+                        Err(err) => {
+                            errors.push(RenderError::MathError {
+                                span,
+                                diagnostics: err.to_vec(),
+                            });
+                            Event::Text("".into())
+                        }
                     }
                 }
-            }
-            pulldown_cmark::Event::DisplayMath(cow_str) => {
-                // TODO: This should parse the cow_str into a Content and somehow convert that to a
-                // page.
-                let f = format!(
-                    "
+                pulldown_cmark::Event::DisplayMath(cow_str) => {
+                    // TODO: This should parse the cow_str into a Content and somehow convert that to a
+                    // page.
+                    let f = format!(
+                        "
                     #set page(width: auto, height: auto, margin: 0em)
                     $ {} $
                     ",
-                    cow_str
-                );
-                let world = TypstWrapperWorld::new(f);
-                match typst::compile(&world).output {
-                    Ok(doc) => {
-                        let svg = typst_svg::svg(&doc.pages[0]);
-                        Event::Html(svg.into())
+                        cow_str
+                    );
+                    let world = TypstWrapperWorld::new(f);
+                    match typst::compile(&world).output {
+                        Ok(doc) => {
+                            let svg = typst_svg::svg(&doc.pages[0]);
+                            Event::Html(svg.into())
+                        }
+                        Err(err) => {
+                            errors.push(RenderError::MathError {
+                                span,
+                                diagnostics: err.to_vec(),
+                            });
+                            Event::Text("".into())
+                        }
                     }
-                    Err(err) => {
-                        errors.extend(err);
-                        Event::Text("".into())
+                }
+                Event::Start(Tag::CodeBlock(CodeBlockKind::Fenced(info))) => {
+                    let lang = code_language(&info);
+                    let written = info
+                        .split(|c: char| c.is_whitespace() || c == ',')
+                        .next()
+                        .unwrap_or_default();
+                    if lang.is_none() && !written.is_empty() {
+                        warnings.push(MarkdownWarning::UnknownCodeLanguage {
+                            span,
+                            language: written.to_string(),
+                        });
                     }
+                    Event::Start(Tag::CodeBlock(CodeBlockKind::Fenced(
+                        lang.unwrap_or_default().into(),
+                    )))
                 }
-            }
-            e => e,
-        });
+                e => e,
+            });
+        let events = html_heading_ids(html_images(
+            html_term_lists(parser.collect()),
+            options.image_dir.as_deref(),
+        ));
         let mut s = String::new();
-        pulldown_cmark::html::push_html(&mut s, parser);
-        if !errors.is_empty() {
-            Err(RenderError::TypstError(errors))?
+        pulldown_cmark::html::push_html(&mut s, events.into_iter());
+        if let Some(err) = errors.into_iter().next() {
+            Err(err)
+        } else {
+            Ok((s, warnings))
+        }
+    }
+
+    /// Checks that this markdown can be rendered, without rendering it
+    ///
+    /// Every raw HTML block or tag (see [`HtmlMode`]), every image, and every maths block that
+    /// fails to compile or is over the default [`MathLimits`] is reported, rather than just the
+    /// first error.
+    pub fn validate(&self) -> Result<(), Vec<RenderError>> {
+        let world = TypstWrapperWorld::new("");
+        let limits = MathLimits::default();
+        let mut errors = Vec::new();
+        let mut in_html_block = false;
+        let mut math_count = 0;
+        for (event, span) in Parser::new_ext(self.raw(), CMARK_OPTIONS).into_offset_iter() {
+            match event {
+                Event::Start(Tag::HtmlBlock) => {
+                    in_html_block = true;
+                    errors.push(RenderError::UnsupportedHtml { span });
+                }
+                Event::End(TagEnd::HtmlBlock) => in_html_block = false,
+                Event::Start(Tag::Image { .. }) => {
+                    errors.push(RenderError::UnsupportedImage { span });
+                }
+                Event::Html(_) | Event::InlineHtml(_) if !in_html_block => {
+                    errors.push(RenderError::UnsupportedHtml { span });
+                }
+                Event::InlineMath(ref math) | Event::DisplayMath(ref math) => {
+                    math_count += 1;
+                    if let Err(err) = limits.check(math, math_count, &span) {
+                        errors.push(err);
+                        continue;
+                    }
+                    let display = matches!(event, Event::DisplayMath(_));
+                    if let Err(diagnostics) = eval_math(&world, math, display) {
+                        errors.push(RenderError::MathError {
+                            span,
+                            diagnostics: diagnostics.to_vec(),
+                        });
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        if errors.is_empty() {
+            Ok(())
         } else {
-            Ok(s)
+            Err(errors)
         }
     }
 
@@ -149,8 +554,394 @@ impl MarkdownRenderable {
     pub fn content(&self, world: &impl World) -> RenderResult<Content> {
         render_markdown(self.raw(), world)
     }
+
+    /// Renders the given string into typst content with the given options (or just an
+    /// [`HtmlMode`])
+    pub fn content_with(
+        &self,
+        world: &impl World,
+        options: impl Into<MarkdownOptions>,
+    ) -> RenderResult<Content> {
+        render_markdown_with(self.raw(), world, options)
+    }
+}
+
+/// Alternative names for code block languages, mapped to the name typst knows them by
+#[cfg(feature = "render")]
+const LANGUAGE_ALIASES: &[(&str, &str)] = &[
+    ("c++", "cpp"),
+    ("cxx", "cpp"),
+    ("golang", "go"),
+    ("js", "javascript"),
+    ("node", "javascript"),
+    ("ml", "ocaml"),
+    ("py", "python"),
+    ("py3", "python"),
+    ("python3", "python"),
+    ("rs", "rust"),
+    ("sh", "bash"),
+    ("shell", "bash"),
+    ("ts", "typescript"),
+];
+
+#[cfg(feature = "render")]
+lazy_static::lazy_static! {
+    /// Every language name and extension for which typst has a syntax, in lowercase
+    static ref KNOWN_LANGUAGES: HashSet<String> = RawElem::languages()
+        .into_iter()
+        .flat_map(|(name, extensions)| std::iter::once(name).chain(extensions))
+        .map(str::to_lowercase)
+        .collect();
 }
 
+/// Normalises the info string of a fenced code block into a language for highlighting
+///
+/// Only the first word of the info string is considered, and common aliases (e.g., `py`) are
+/// resolved to their canonical name.  Returns `None` if the language is not known, in which
+/// case the block should be rendered as plain text.
+#[cfg(feature = "render")]
+pub fn code_language(info: &str) -> Option<&'static str> {
+    let lang = info
+        .split(|c: char| c.is_whitespace() || c == ',')
+        .next()?
+        .to_lowercase();
+    let lang = LANGUAGE_ALIASES
+        .iter()
+        .find(|(alias, _)| *alias == lang)
+        .map_or(lang.as_str(), |(_, name)| name);
+    KNOWN_LANGUAGES.get(lang).map(String::as_str)
+}
+
+/// The rendered content of a markdown table cell as a typst table cell
+///
+/// Markdown table cells are always rendered as [`TableCell`]s, but anything else is wrapped in
+/// one rather than panicking.
+#[cfg(feature = "render")]
+fn table_cell(content: Content) -> Packed<TableCell> {
+    content
+        .into_packed::<TableCell>()
+        .unwrap_or_else(|content| Packed::new(TableCell::new(content)))
+}
+
+/// Separates a term from its description in a description list
+///
+/// A paragraph in which every line is of the form `term :: description` is rendered as a
+/// description list (`terms` in typst, `<dl>` in HTML), for example:
+///
+/// ```markdown
+/// Input :: A single integer $n$
+/// Output :: The value of $n^2$
+/// ```
+///
+/// The separator must be surrounded by spaces and must not be inside any formatting (such as
+/// `*emphasis*`).  Either side may contain formatting.
+#[cfg(feature = "render")]
+const TERM_SEPARATOR: &str = " :: ";
+
+/// Split the trees of a paragraph into `(term, description)` pairs, if it is a description list
+///
+/// See [`TERM_SEPARATOR`].
+#[cfg(feature = "render")]
+fn split_terms<'a>(trees: &[Tree<'a>]) -> Option<Vec<(Vec<Tree<'a>>, Vec<Tree<'a>>)>> {
+    trees
+        .split(|t| matches!(t, Tree::SoftBreak(_) | Tree::HardBreak(_)))
+        .map(|line| {
+            let (i, text) = line.iter().enumerate().find_map(|(i, t)| match t {
+                Tree::Text(text) if text.item.contains(TERM_SEPARATOR) => Some((i, text)),
+                _ => None,
+            })?;
+            let (term, description) = text.item.split_once(TERM_SEPARATOR)?;
+            let text_tree = |s: &str| {
+                Tree::Text(Spanned {
+                    item: s.to_string().into(),
+                    span: text.span.clone(),
+                })
+            };
+
+            let mut t = line[..i].to_vec();
+            if !term.is_empty() {
+                t.push(text_tree(term));
+            }
+            let mut d = Vec::new();
+            if !description.is_empty() {
+                d.push(text_tree(description));
+            }
+            d.extend_from_slice(&line[i + 1..]);
+            Some((t, d))
+        })
+        .collect()
+}
+
+/// Split the events of a paragraph into `(term, description)` pairs, if it is a description
+/// list
+///
+/// See [`TERM_SEPARATOR`].
+#[cfg(feature = "render")]
+fn split_term_events<'a>(events: &[Event<'a>]) -> Option<Vec<(Vec<Event<'a>>, Vec<Event<'a>>)>> {
+    // like `split_terms`, lines are only split at top-level breaks, so a line break inside
+    // formatting (e.g., emphasis) does not start a new term
+    let mut lines = Vec::new();
+    let mut depth = 0usize;
+    let mut start = 0;
+    for (i, e) in events.iter().enumerate() {
+        match e {
+            Event::Start(_) => depth += 1,
+            Event::End(_) => depth = depth.checked_sub(1)?,
+            Event::SoftBreak | Event::HardBreak if depth == 0 => {
+                lines.push(&events[start..i]);
+                start = i + 1;
+            }
+            _ => {}
+        }
+    }
+    if depth != 0 {
+        return None;
+    }
+    lines.push(&events[start..]);
+
+    lines
+        .into_iter()
+        .map(|line| {
+            let mut depth = 0usize;
+            let mut found = None;
+            for (i, e) in line.iter().enumerate() {
+                match e {
+                    Event::Start(_) => depth += 1,
+                    Event::End(_) => depth = depth.checked_sub(1)?,
+                    Event::Text(text)
+                        if found.is_none() && depth == 0 && text.contains(TERM_SEPARATOR) =>
+                    {
+                        found = Some((i, text))
+                    }
+                    _ => {}
+                }
+            }
+            if depth != 0 {
+                return None;
+            }
+            let (i, text) = found?;
+            let (term, description) = text.split_once(TERM_SEPARATOR)?;
+
+            let mut t = line[..i].to_vec();
+            if !term.is_empty() {
+                t.push(Event::Text(term.to_string().into()));
+            }
+            let mut d = Vec::new();
+            if !description.is_empty() {
+                d.push(Event::Text(description.to_string().into()));
+            }
+            d.extend_from_slice(&line[i + 1..]);
+            Some((t, d))
+        })
+        .collect()
+}
+
+/// Get the `src` for an image in HTML
+///
+/// `data:` and web URIs are used as is, while local files within `image_dir` are embedded as
+/// `data:` URIs so that the HTML is self-contained.  Returns `None` if the image cannot be
+/// displayed.
+#[cfg(feature = "render")]
+fn image_source(dest: &str, image_dir: Option<&Path>) -> Option<String> {
+    if dest.starts_with("data:") || dest.starts_with("https://") || dest.starts_with("http://") {
+        return Some(dest.into());
+    }
+
+    // any other scheme is unsupported, but be careful not to treat `C:\` as a scheme
+    if dest
+        .split_once(':')
+        .is_some_and(|(scheme, _)| scheme.len() > 1 && !scheme.contains(['/', '\\']))
+    {
+        return None;
+    }
+
+    let path = Path::new(dest);
+    let mime = match path.extension()?.to_str()?.to_lowercase().as_str() {
+        "png" => "image/png",
+        "jpg" | "jpeg" => "image/jpeg",
+        "gif" => "image/gif",
+        "svg" => "image/svg+xml",
+        "webp" => "image/webp",
+        _ => return None,
+    };
+    let bytes = std::fs::read(local_image(image_dir?, path)?).ok()?;
+    Some(format!(
+        "data:{};base64,{}",
+        mime,
+        BASE64_STANDARD.encode(bytes)
+    ))
+}
+
+/// Resolve `path` within `dir`, if it is a relative path that does not leave `dir` (including
+/// through symlinks)
+#[cfg(feature = "render")]
+fn local_image(dir: &Path, path: &Path) -> Option<PathBuf> {
+    use std::path::Component;
+
+    if !path
+        .components()
+        .all(|c| matches!(c, Component::Normal(_) | Component::CurDir))
+    {
+        return None;
+    }
+    let dir = dir.canonicalize().ok()?;
+    let path = dir.join(path).canonicalize().ok()?;
+    path.starts_with(&dir).then_some(path)
+}
+
+/// Resolve the sources of images, replacing images that cannot be displayed with their alt text
+#[cfg(feature = "render")]
+fn html_images<'a>(events: Vec<Event<'a>>, image_dir: Option<&Path>) -> Vec<Event<'a>> {
+    // whether each image that is currently open was kept
+    let mut open = Vec::new();
+    events
+        .into_iter()
+        .filter_map(|event| match event {
+            Event::Start(Tag::Image {
+                link_type,
+                dest_url,
+                title,
+                id,
+            }) => {
+                let src = image_source(&dest_url, image_dir);
+                open.push(src.is_some());
+                src.map(|src| {
+                    Event::Start(Tag::Image {
+                        link_type,
+                        dest_url: src.into(),
+                        title,
+                        id,
+                    })
+                })
+            }
+            Event::End(TagEnd::Image) => open
+                .pop()
+                .unwrap_or(true)
+                .then_some(Event::End(TagEnd::Image)),
+            e => Some(e),
+        })
+        .collect()
+}
+
+/// Turn the text of a heading into an `id`, in the same way as GitHub
+///
+/// The text is lowercased, punctuation is removed, and spaces become hyphens.
+#[cfg(feature = "render")]
+fn slugify(text: &str) -> String {
+    text.trim()
+        .to_lowercase()
+        .chars()
+        .filter_map(|c| match c {
+            ' ' => Some('-'),
+            c if c.is_alphanumeric() || c == '-' || c == '_' => Some(c),
+            _ => None,
+        })
+        .collect()
+}
+
+/// Give each heading an `id` so that it can be linked to
+///
+/// Headings with the same text get ids with a `-1`, `-2`, ... suffix, as on GitHub.
+#[cfg(feature = "render")]
+fn html_heading_ids(mut events: Vec<Event<'_>>) -> Vec<Event<'_>> {
+    let mut used = HashSet::new();
+    for i in 0..events.len() {
+        let Event::Start(Tag::Heading { id: None, .. }) = &events[i] else {
+            continue;
+        };
+        let text = events[i + 1..]
+            .iter()
+            .take_while(|e| !matches!(e, Event::End(TagEnd::Heading(_))))
+            .filter_map(|e| match e {
+                Event::Text(text) | Event::Code(text) => Some(text.as_ref()),
+                _ => None,
+            })
+            .collect::<String>();
+        let slug = slugify(&text);
+        let slug = std::iter::once(slug.clone())
+            .chain((1..).map(|n| format!("{}-{}", slug, n)))
+            .find(|s| !used.contains(s))
+            .expect("there are infinitely many suffixes");
+        used.insert(slug.clone());
+        if let Event::Start(Tag::Heading { id, .. }) = &mut events[i] {
+            *id = Some(slug.into());
+        }
+    }
+    events
+}
+
+/// Replace paragraphs that are description lists with `<dl>` elements
+#[cfg(feature = "render")]
+fn html_term_lists(events: Vec<Event<'_>>) -> Vec<Event<'_>> {
+    let mut out = Vec::with_capacity(events.len());
+    let mut events = events.into_iter();
+    while let Some(event) = events.next() {
+        if event != Event::Start(Tag::Paragraph) {
+            out.push(event);
+            continue;
+        }
+
+        let paragraph = events
+            .by_ref()
+            .take_while(|e| *e != Event::End(TagEnd::Paragraph))
+            .collect::<Vec<_>>();
+        if let Some(items) = split_term_events(&paragraph) {
+            out.push(Event::Html("<dl>\n".into()));
+            for (term, description) in items {
+                out.push(Event::Html("<dt>".into()));
+                out.extend(term);
+                out.push(Event::Html("</dt>\n<dd>".into()));
+                out.extend(description);
+                out.push(Event::Html("</dd>\n".into()));
+            }
+            out.push(Event::Html("</dl>\n".into()));
+        } else {
+            out.push(Event::Start(Tag::Paragraph));
+            out.extend(paragraph);
+            out.push(Event::End(TagEnd::Paragraph));
+        }
+    }
+    out
+}
+
+/// How thick horizontal rules (`---`) are, in points
+#[cfg(feature = "render")]
+const RULE_THICKNESS: f64 = 0.5;
+
+/// Evaluate a maths block into typst content
+///
+/// Display maths is centred on its own line, as in typst's `$ ... $`.
+#[cfg(feature = "render")]
+fn eval_math(
+    world: &dyn World,
+    math: &str,
+    display: bool,
+) -> Result<Content, EcoVec<SourceDiagnostic>> {
+    let val = if display {
+        typst::eval::eval_string(
+            world.track(),
+            &format!("$ {} $", math.trim()),
+            Span::detached(),
+            typst::eval::EvalMode::Markup,
+            world.library().math.scope().clone(),
+        )?
+    } else {
+        typst::eval::eval_string(
+            world.track(),
+            math,
+            Span::detached(),
+            typst::eval::EvalMode::Math,
+            Scope::new(),
+        )?
+    };
+
+    match val {
+        Value::Content(content) => Ok(content),
+        _ => unreachable!(),
+    }
+}
+
+#[cfg(feature = "render")]
 fn map_align(a: &Alignment) -> Smart<typst::layout::Alignment> {
     match a {
         Alignment::None => Smart::Auto,
@@ -166,24 +957,48 @@ fn map_align(a: &Alignment) -> Smart<typst::layout::Alignment> {
     }
 }
 
+#[cfg(feature = "render")]
 struct TypstMarkdownRenderer<'a> {
     world: &'a dyn World,
+    options: MarkdownOptions,
 }
 
+#[cfg(feature = "render")]
 impl<'a> TypstMarkdownRenderer<'a> {
-    fn new(world: &'a dyn World) -> Self {
-        Self { world }
+    fn new(world: &'a dyn World, options: MarkdownOptions) -> Self {
+        Self { world, options }
+    }
+
+    fn render_html(&self, span: Range<usize>) -> RenderResult<Content> {
+        match self.options.html {
+            HtmlMode::Error => Err(RenderError::UnsupportedHtml { span }),
+            HtmlMode::Strip => Ok(Content::empty()),
+        }
     }
 
     fn render_tree(&self, tree: Tree) -> RenderResult<Content> {
         match tree {
             Tree::Group(g) => match g.tag.item {
-                Tag::Paragraph => Ok(Content::sequence(
-                    std::iter::once(Ok(Content::new(ParbreakElem::new())))
-                        .chain(g.stream.0.into_iter().map(|t| self.render_tree(t)))
-                        .chain(std::iter::once(Ok(Content::new(ParbreakElem::new()))))
-                        .collect::<RenderResult<Vec<_>>>()?,
-                )),
+                Tag::Paragraph => {
+                    if let Some(items) = split_terms(&g.stream.0) {
+                        let items = items
+                            .into_iter()
+                            .map(|(term, description)| {
+                                Ok(Packed::new(TermItem::new(
+                                    self.render_ast(Ast(term))?,
+                                    self.render_ast(Ast(description))?,
+                                )))
+                            })
+                            .collect::<RenderResult<Vec<_>>>()?;
+                        return Ok(Content::new(TermsElem::new(items)));
+                    }
+                    Ok(Content::sequence(
+                        std::iter::once(Ok(Content::new(ParbreakElem::new())))
+                            .chain(g.stream.0.into_iter().map(|t| self.render_tree(t)))
+                            .chain(std::iter::once(Ok(Content::new(ParbreakElem::new()))))
+                            .collect::<RenderResult<Vec<_>>>()?,
+                    ))
+                }
                 Tag::Heading { level, .. } => Ok(Content::new(
                     HeadingElem::new(self.render_ast(g.stream)?).with_level(
                         typst::foundations::Smart::Custom(
@@ -191,36 +1006,71 @@ impl<'a> TypstMarkdownRenderer<'a> {
                         ),
                     ),
                 )),
-                Tag::BlockQuote(_) => {
-                    // Blockquote ~ #figure()
-                    // TODO: use block quote kind somehow?
+                Tag::BlockQuote(kind) => {
+                    let label = kind.map(|kind| {
+                        let (label, colour) = match kind {
+                            BlockQuoteKind::Note => {
+                                ("Note", Color::from_u8(0x09, 0x69, 0xda, 0xff))
+                            }
+                            BlockQuoteKind::Tip => ("Tip", Color::from_u8(0x1a, 0x7f, 0x37, 0xff)),
+                            BlockQuoteKind::Important => {
+                                ("Important", Color::from_u8(0x82, 0x50, 0xdf, 0xff))
+                            }
+                            BlockQuoteKind::Warning => {
+                                ("Warning", Color::from_u8(0x9a, 0x67, 0x00, 0xff))
+                            }
+                            BlockQuoteKind::Caution => {
+                                ("Caution", Color::from_u8(0xd1, 0x24, 0x2f, 0xff))
+                            }
+                        };
+                        (
+                            TextElem::packed(label)
+                                .strong()
+                                .styled(TextElem::set_fill(colour.into())),
+                            colour,
+                        )
+                    });
+                    let (label, colour) = match label {
+                        Some((label, colour)) => (Some(label), colour),
+                        None => (None, Color::GRAY),
+                    };
                     let content = Content::sequence(
-                        std::iter::once(Ok(Content::new(ParbreakElem::new())))
+                        label
+                            .into_iter()
+                            .map(Ok)
                             .chain(g.stream.0.into_iter().map(|t| self.render_tree(t)))
-                            .chain(std::iter::once(Ok(Content::new(ParbreakElem::new()))))
                             .collect::<RenderResult<Vec<_>>>()?,
                     );
-                    Ok(Content::new(FigureElem::new(content.aligned(
-                        typst::layout::Alignment::H(typst::layout::HAlignment::Left),
-                    ))))
+                    let stroke = Stroke::from_pair(colour, Length::from(Abs::pt(2.)));
+                    Ok(Content::new(
+                        BlockElem::new()
+                            .with_inset(Sides::new(
+                                Some(Length::from(Em::new(0.75)).into()),
+                                Some(Length::from(Em::new(0.25)).into()),
+                                None,
+                                Some(Length::from(Em::new(0.25)).into()),
+                            ))
+                            .with_stroke(Sides::new(Some(Some(stroke)), None, None, None))
+                            .with_body(Some(BlockBody::Content(content))),
+                    ))
                 }
                 Tag::CodeBlock(code_block_kind) => {
                     let content = self.render_ast_to_text(g.stream);
                     let elem = RawElem::new(RawContent::Text(content)).with_block(true);
                     let elem = match code_block_kind {
                         CodeBlockKind::Indented => elem,
-                        CodeBlockKind::Fenced(s) => {
-                            if s.is_empty() {
-                                elem
-                            } else {
-                                elem.with_lang(Some(s.as_ref().into()))
-                            }
-                        }
+                        CodeBlockKind::Fenced(s) => match code_language(&s) {
+                            Some(lang) => elem.with_lang(Some(lang.into())),
+                            None => elem,
+                        },
                     };
                     Ok(Content::new(FigureElem::new(Content::new(elem))))
                 }
-                Tag::HtmlBlock => Err(RenderError::UnsupportedHtml),
+                Tag::HtmlBlock => self.render_html(g.tag.span.0),
                 Tag::List(ord) => {
+                    // CommonMark only uses the number of the first item (e.g., `5.` followed by
+                    // `1.` is numbered 5, 6), so each item's number is counted from the start.  A
+                    // list that restarts is a separate `Tag::List` with its own start.
                     if let Some(ord) = ord {
                         let packed = g
                             .stream
@@ -270,11 +1120,7 @@ impl<'a> TypstMarkdownRenderer<'a> {
                         header
                             .0
                             .into_iter()
-                            .map(|t| {
-                                self.render_tree(t)
-                                    .map(|c| c.into_packed().unwrap())
-                                    .map(TableItem::Cell)
-                            })
+                            .map(|t| self.render_tree(t).map(table_cell).map(TableItem::Cell))
                             .collect::<RenderResult<_>>()?,
                     ))));
 
@@ -286,19 +1132,40 @@ impl<'a> TypstMarkdownRenderer<'a> {
                             },
                             _ => unreachable!(),
                         };
-                        children.extend_from_slice(
-                            &row.into_iter()
-                                .map(|t| {
-                                    self.render_tree(t)
-                                        .map(|c| c.into_packed().unwrap())
-                                        .map(TableItem::Cell)
-                                        .map(TableChild::Item)
-                                })
-                                .collect::<RenderResult<Vec<_>>>()?,
-                        );
+                        // every row must have a cell for each column, or the rows after it
+                        // would be shifted, so short rows are padded and long rows are cut off
+                        let mut cells = row
+                            .into_iter()
+                            .take(cols)
+                            .map(|t| self.render_tree(t).map(table_cell))
+                            .collect::<RenderResult<Vec<_>>>()?;
+                        cells.resize_with(cols, || Packed::new(TableCell::new(Content::empty())));
+                        children
+                            .extend(cells.into_iter().map(TableItem::Cell).map(TableChild::Item));
                     }
 
-                    let columns = (0..cols).map(|_| Sizing::Auto).collect::<Vec<_>>();
+                    let columns = match self.options.tables {
+                        TableWidths::Auto => (0..cols).map(|_| Sizing::Auto).collect::<Vec<_>>(),
+                        TableWidths::Fit => {
+                            // the longest text in each column, so that wordy columns get more room
+                            let mut lengths = vec![1; cols];
+                            let cells = children.iter().flat_map(|child| match child {
+                                TableChild::Header(header) => header.children.iter().collect(),
+                                TableChild::Item(item) => vec![item],
+                                TableChild::Footer(_) => vec![],
+                            });
+                            for (i, cell) in cells.enumerate() {
+                                if let TableItem::Cell(cell) = cell {
+                                    let length = cell.body.plain_text().chars().count();
+                                    lengths[i % cols] = lengths[i % cols].max(length);
+                                }
+                            }
+                            lengths
+                                .into_iter()
+                                .map(|l| Sizing::Fr(Fr::new(l as f64)))
+                                .collect()
+                        }
+                    };
 
                     Ok(Content::new(FigureElem::new(Content::new(
                         TableElem::new(children)
@@ -311,11 +1178,7 @@ impl<'a> TypstMarkdownRenderer<'a> {
                         .stream
                         .0
                         .into_iter()
-                        .map(|t| {
-                            self.render_tree(t)
-                                .map(|c| c.into_packed().unwrap())
-                                .map(TableItem::Cell)
-                        })
+                        .map(|t| self.render_tree(t).map(table_cell).map(TableItem::Cell))
                         .collect::<RenderResult<_>>()?;
                     Ok(Content::new(TableHeader::new(items)))
                 }
@@ -323,11 +1186,7 @@ impl<'a> TypstMarkdownRenderer<'a> {
                     .stream
                     .0
                     .into_iter()
-                    .map(|t| {
-                        self.render_tree(t)
-                            .map(|c| c.into_packed().unwrap())
-                            .map(TableItem::Cell)
-                    })
+                    .map(|t| self.render_tree(t).map(table_cell).map(TableItem::Cell))
                     .collect::<RenderResult<_>>()
                     .map(TableHeader::new)
                     .map(Content::new),
@@ -347,56 +1206,51 @@ impl<'a> TypstMarkdownRenderer<'a> {
                     )),
                     self.render_ast(g.stream)?,
                 ))),
-                Tag::Image { .. } => todo!(),
+                Tag::Image { .. } => Err(RenderError::UnsupportedImage { span: g.tag.span.0 }),
                 Tag::MetadataBlock(_) => unreachable!("Feature is disabled"),
             },
             Tree::Text(spanned) => Ok(Content::new(TextElem::new(spanned.item.as_ref().into()))),
-            Tree::Code(spanned) => Ok(Content::new(RawElem::new(RawContent::Text(
-                spanned.item.as_ref().into(),
-            )))),
-            Tree::Html(_) => Err(RenderError::UnsupportedHtml),
-            Tree::InlineHtml(_) => Err(RenderError::UnsupportedHtml),
+            // Inline code is a raw element so that its contents (e.g. `$` or `*`) are never
+            // interpreted as maths or markup
+            Tree::Code(spanned) => Ok(Content::new(
+                RawElem::new(RawContent::Text(spanned.item.as_ref().into())).with_block(false),
+            )),
+            Tree::Html(spanned) | Tree::InlineHtml(spanned) => self.render_html(spanned.span.0),
             Tree::FootnoteReference(_) => unreachable!("Feature is disabled"),
             Tree::SoftBreak(_) => Ok(Content::new(SpaceElem::new())),
             Tree::HardBreak(_) => Ok(Content::new(LinebreakElem::new())),
-            Tree::Rule(_) => Ok(Content::new(LineElem::new().with_length(
-                typst::layout::Rel {
-                    rel: Ratio::new(1.),
-                    abs: Length::zero(),
-                },
-            ))),
+            Tree::Rule(_) => {
+                // a block so that the rule is on its own line, spanning the whole width, rather
+                // than being laid out inline
+                let line = LineElem::new()
+                    .with_length(typst::layout::Rel {
+                        rel: Ratio::new(1.),
+                        abs: Length::zero(),
+                    })
+                    .with_stroke(Stroke::from_pair(
+                        Color::GRAY,
+                        Length::from(Abs::pt(RULE_THICKNESS)),
+                    ));
+                Ok(Content::new(
+                    BlockElem::new().with_body(Some(BlockBody::Content(Content::new(line)))),
+                ))
+            }
             Tree::TaskListMarker(_) => unreachable!("Feature is disabled"),
             Tree::InlineMath(spanned) => {
-                let content = spanned.item;
-
-                let val = typst::eval::eval_string(
-                    self.world.track(),
-                    &content,
-                    Span::detached(),
-                    typst::eval::EvalMode::Math,
-                    Scope::new(),
-                )?;
-
-                match val {
-                    Value::Content(content) => Ok(content),
-                    _ => unreachable!(),
-                }
+                eval_math(self.world, &spanned.item, false).map_err(|diagnostics| {
+                    RenderError::MathError {
+                        span: spanned.span.0,
+                        diagnostics: diagnostics.to_vec(),
+                    }
+                })
             }
             Tree::DisplayMath(spanned) => {
-                let content = spanned.item.trim();
-
-                let val = typst::eval::eval_string(
-                    self.world.track(),
-                    &format!("$ {} $", content),
-                    Span::detached(),
-                    typst::eval::EvalMode::Markup,
-                    self.world.library().math.scope().clone(),
-                )?;
-
-                match val {
-                    Value::Content(content) => Ok(content),
-                    _ => unreachable!(),
-                }
+                eval_math(self.world, &spanned.item, true).map_err(|diagnostics| {
+                    RenderError::MathError {
+                        span: spanned.span.0,
+                        diagnostics: diagnostics.to_vec(),
+                    }
+                })
             }
         }
     }
@@ -414,9 +1268,11 @@ impl<'a> TypstMarkdownRenderer<'a> {
         let mut s = EcoString::new();
         for t in ast.0 {
             match t {
-                Tree::Text(spanned) => {
+                Tree::Text(spanned) | Tree::Code(spanned) => {
                     s.push_str(&spanned.item);
                 }
+                Tree::SoftBreak(_) | Tree::HardBreak(_) => s.push('\n'),
+                Tree::Group(g) => s.push_str(&self.render_ast_to_text(g.stream)),
                 s => unreachable!("need to impl {:?}", s),
             }
         }
@@ -430,6 +1286,17 @@ impl<'a> TypstMarkdownRenderer<'a> {
     }
 }
 
+#[cfg(feature = "render")]
 pub fn render_markdown(markdown: impl AsRef<str>, world: &impl World) -> RenderResult<Content> {
-    TypstMarkdownRenderer::new(world).render(markdown)
+    render_markdown_with(markdown, world, HtmlMode::default())
+}
+
+/// Render markdown into typst content with the given options (or just an [`HtmlMode`])
+#[cfg(feature = "render")]
+pub fn render_markdown_with(
+    markdown: impl AsRef<str>,
+    world: &impl World,
+    options: impl Into<MarkdownOptions>,
+) -> RenderResult<Content> {
+    TypstMarkdownRenderer::new(world, options.into()).render(markdown)
 }
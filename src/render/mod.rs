@@ -1,2 +1,95 @@
+#[cfg(feature = "render")]
+pub mod html;
 pub mod markdown;
+#[cfg(feature = "render")]
 pub mod typst;
+
+#[cfg(feature = "render")]
+use self::{
+    markdown::{HtmlMode, MarkdownOptions, RenderError, TableWidths},
+    typst::FontConfig,
+};
+#[cfg(feature = "render")]
+use ::typst::diag::SourceDiagnostic;
+
+/// Options for rendering a [`Config`](crate::Config) to a PDF
+#[cfg(feature = "render")]
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct RenderOptions {
+    /// Which fonts are available to the template
+    pub fonts: FontConfig,
+    /// The time used by the template (e.g., for `datetime.today()`) and the PDF's creation date
+    ///
+    /// If `None`, the current time is used for the template and no creation date is written, so
+    /// fixing this makes the output reproducible.
+    pub time: Option<time::OffsetDateTime>,
+    /// Order `#problems` from easiest to hardest (see
+    /// [`Packet::sorted_by_difficulty`](crate::packet::Packet::sorted_by_difficulty)) rather
+    /// than in the order in which they are listed
+    pub sort_by_difficulty: bool,
+    /// What to do with raw HTML in the preamble and problem descriptions
+    pub html: HtmlMode,
+    /// How the columns of tables in the preamble and problem descriptions are sized
+    pub tables: TableWidths,
+    /// Where the template can import typst packages from, if it may use them at all
+    #[cfg(feature = "packages")]
+    pub packages: Option<typst::PackageConfig>,
+}
+
+#[cfg(feature = "render")]
+impl RenderOptions {
+    /// The options for rendering the markdown in the packet
+    pub(crate) fn markdown(&self) -> MarkdownOptions {
+        MarkdownOptions {
+            html: self.html,
+            tables: self.tables,
+            ..Default::default()
+        }
+    }
+}
+
+/// Why a [`Config`](crate::Config) could not be rendered to a PDF
+#[cfg(feature = "render")]
+#[derive(Debug, thiserror::Error, miette::Diagnostic)]
+pub enum RenderPdfError {
+    /// A font could not be read, or the PDF could not be written
+    #[error(transparent)]
+    Io(#[from] std::io::Error),
+    /// The preamble could not be rendered
+    #[error("Failed to render the preamble: {0}")]
+    Preamble(RenderError),
+    /// The descriptions of some problems could not be rendered, as pairs of the problem's title
+    /// and the error
+    ///
+    /// Rather than leaving these problems out of the packet, nothing is rendered.
+    #[error("Failed to render {}", problem_errors(.0))]
+    Problems(Vec<(String, RenderError)>),
+    /// The template failed to compile, or the PDF could not be exported
+    #[error("Error while processing typst: {0:?}")]
+    Typst(Vec<SourceDiagnostic>),
+}
+
+#[cfg(feature = "render")]
+fn problem_errors(errs: &[(String, RenderError)]) -> String {
+    errs.iter()
+        .map(|(title, err)| format!("problem '{}': {}", title, err))
+        .collect::<Vec<_>>()
+        .join("; ")
+}
+
+#[cfg(feature = "render")]
+impl From<ecow::EcoVec<SourceDiagnostic>> for RenderPdfError {
+    fn from(value: ecow::EcoVec<SourceDiagnostic>) -> Self {
+        Self::Typst(value.to_vec())
+    }
+}
+
+#[cfg(feature = "render")]
+impl From<RenderPdfError> for std::io::Error {
+    fn from(val: RenderPdfError) -> Self {
+        match val {
+            RenderPdfError::Io(err) => err,
+            err => std::io::Error::other(format!("{}", err)),
+        }
+    }
+}
@@ -1,16 +1,26 @@
 // Adapted from: https://github.com/tfachmann/typst-as-library/blob/main/src/lib.rs
 
 use std::collections::HashMap;
-use std::sync::{Arc, Mutex};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex, MutexGuard, PoisonError};
 
 use comemo::track;
-use typst::diag::{FileError, FileResult};
+#[cfg(feature = "packages")]
+use typst::diag::PackageResult;
+use typst::diag::{FileError, FileResult, PackageError};
 use typst::foundations::{Bytes, Datetime};
-use typst::syntax::{FileId, Source};
+#[cfg(feature = "packages")]
+use typst::syntax::package::PackageSpec;
+use typst::syntax::{FileId, Source, VirtualPath};
 use typst::text::{Font, FontBook};
 use typst::utils::LazyHash;
 use typst::{Library, World};
 use typst_kit::fonts::{FontSlot, Fonts};
+#[cfg(feature = "packages")]
+use typst_kit::{
+    download::{Downloader, ProgressSink},
+    package::PackageStorage,
+};
 
 /// This struct is needed so we can return a single value from the `lazy_static`
 struct FontsHolder {
@@ -18,12 +28,84 @@ struct FontsHolder {
     fonts: Vec<FontSlot>,
 }
 
+impl FontsHolder {
+    fn search(config: &FontConfig) -> Self {
+        let fonts = Fonts::searcher()
+            .include_system_fonts(config.include_system)
+            .search_with(&config.extra_dirs);
+        FontsHolder {
+            book: fonts.book.into(),
+            fonts: fonts.fonts,
+        }
+    }
+}
+
 lazy_static::lazy_static! {
-    static ref FONTS: FontsHolder = {
-        // TODO: System fonts? Adds significant delay and may not be necessary.
-        let fonts = Fonts::searcher().include_system_fonts(false).search();
-        FontsHolder { book: fonts.book.into(), fonts: fonts.fonts }
-    };
+    static ref FONTS: Arc<FontsHolder> = Arc::new(FontsHolder::search(&FontConfig::default()));
+}
+
+/// Configuration for which fonts are available to typst
+///
+/// The fonts embedded in typst are always available.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Default)]
+pub struct FontConfig {
+    /// Whether to search for fonts installed on the system
+    ///
+    /// This adds significant delay, so it is off by default.
+    pub include_system: bool,
+    /// Extra directories that will be searched for fonts
+    ///
+    /// These are given by the program rendering the packet rather than the config, so they are
+    /// relative to the directory in which it is running.  Fonts from the config (see
+    /// [`RenderConfig::fonts`](crate::RenderConfig::fonts)) are relative to the config file.
+    pub extra_dirs: Vec<PathBuf>,
+}
+
+/// Configuration for where typst packages (e.g., `@preview/cetz:0.3.1`) are found
+///
+/// Packages are looked for in [`PackageConfig::package_dir`] and then in
+/// [`PackageConfig::cache_dir`].  `@preview` packages which are in neither are only downloaded
+/// (into the cache) if [`PackageConfig::allow_downloads`] is set.
+#[cfg(feature = "packages")]
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Default)]
+pub struct PackageConfig {
+    /// The directory of local packages, laid out as `{namespace}/{name}/{version}`
+    ///
+    /// Defaults to typst's usual data directory (e.g., `~/.local/share/typst/packages`).
+    pub package_dir: Option<PathBuf>,
+    /// The directory into which downloaded packages are cached
+    ///
+    /// Defaults to typst's usual cache directory (e.g., `~/.cache/typst/packages`).
+    pub cache_dir: Option<PathBuf>,
+    /// Whether `@preview` packages may be downloaded from the typst registry
+    pub allow_downloads: bool,
+}
+
+/// The packages available to a world, see [`TypstWrapperWorld::with_packages`]
+#[cfg(feature = "packages")]
+struct Packages {
+    storage: PackageStorage,
+    allow_downloads: bool,
+}
+
+#[cfg(feature = "packages")]
+impl Packages {
+    /// The directory containing the package, downloading it if that is allowed
+    fn prepare(&self, spec: &PackageSpec) -> PackageResult<PathBuf> {
+        if !self.allow_downloads {
+            let subdir = format!("{}/{}/{}", spec.namespace, spec.name, spec.version);
+            return [
+                self.storage.package_path(),
+                self.storage.package_cache_path(),
+            ]
+            .into_iter()
+            .flatten()
+            .map(|dir| dir.join(&subdir))
+            .find(|dir| dir.exists())
+            .ok_or_else(|| PackageError::NotFound(spec.clone()));
+        }
+        self.storage.prepare_package(spec, &mut ProgressSink)
+    }
 }
 
 /// Main interface that determines the environment for Typst.
@@ -39,6 +121,19 @@ pub struct TypstWrapperWorld {
 
     /// Map of all known files.
     files: Arc<Mutex<HashMap<FileId, FileEntry>>>,
+
+    /// Fonts available to this world.
+    fonts: Arc<FontsHolder>,
+
+    /// Fonts added with [`TypstWrapperWorld::add_font_file`], indexed after `fonts`.
+    extra_fonts: Vec<Font>,
+
+    /// Book containing both `fonts` and `extra_fonts`, if there are any extra fonts.
+    book: Option<LazyHash<FontBook>>,
+
+    /// Where to find packages, if they are enabled.
+    #[cfg(feature = "packages")]
+    packages: Option<Arc<Packages>>,
 }
 
 impl TypstWrapperWorld {
@@ -48,24 +143,119 @@ impl TypstWrapperWorld {
             source: Source::detached(source),
             time: time::OffsetDateTime::now_utc(),
             files: Arc::new(Mutex::new(HashMap::new())),
+            fonts: Arc::clone(&FONTS),
+            extra_fonts: Vec::new(),
+            book: None,
+            #[cfg(feature = "packages")]
+            packages: None,
         }
     }
 
+    /// Create a world which searches for fonts according to `config`
+    ///
+    /// The default config reuses the fonts that are shared by all worlds, so it does not search
+    /// again.
+    pub fn with_fonts(source: impl Into<String>, config: &FontConfig) -> Self {
+        let mut world = Self::new(source);
+        if *config != FontConfig::default() {
+            world.fonts = Arc::new(FontsHolder::search(config));
+        }
+        world
+    }
+
+    /// Let the template import typst packages, found according to `config`
+    #[cfg(feature = "packages")]
+    pub fn with_packages(mut self, config: &PackageConfig) -> Self {
+        let downloader = Downloader::new(concat!("bedrock/", env!("CARGO_PKG_VERSION")));
+        self.packages = Some(Arc::new(Packages {
+            storage: PackageStorage::new(
+                config.cache_dir.clone(),
+                config.package_dir.clone(),
+                downloader,
+            ),
+            allow_downloads: config.allow_downloads,
+        }));
+        self
+    }
+
+    /// Fix the world's clock to `time`, so that `datetime.today()` is reproducible
+    pub fn with_time(mut self, time: time::OffsetDateTime) -> Self {
+        self.time = time;
+        self
+    }
+
+    /// Load all fonts in the file at `path` so that they are available to the template
+    pub fn add_font_file(&mut self, path: impl AsRef<Path>) -> std::io::Result<()> {
+        let path = path.as_ref();
+        let bytes = std::fs::read(path).map_err(|e| {
+            std::io::Error::new(
+                e.kind(),
+                format!("Failed to read font {}: {}", path.display(), e),
+            )
+        })?;
+
+        let fonts = Font::iter(bytes.into()).collect::<Vec<_>>();
+        if fonts.is_empty() {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::InvalidData,
+                format!("{} is not a valid font file", path.display()),
+            ));
+        }
+
+        let book = self
+            .book
+            .get_or_insert_with(|| LazyHash::new((*self.fonts.book).clone()));
+        for font in fonts {
+            book.push(font.info().clone());
+            self.extra_fonts.push(font);
+        }
+        Ok(())
+    }
+
+    /// Make a file available to the template at `path` without reading it from disk
+    ///
+    /// For example, after `world.insert_file("logo.png", bytes)`, the template can use
+    /// `#image("logo.png")`.  This replaces any file already at `path`, including one which was
+    /// read from disk.
+    pub fn insert_file(&self, path: &str, bytes: Vec<u8>) {
+        let id = FileId::new(None, VirtualPath::new(path));
+        self.lock_files().insert(id, FileEntry::new(bytes, None));
+    }
+
+    /// Lock the files which have been read, even if another thread panicked while holding the
+    /// lock, since every entry is inserted whole
+    fn lock_files(&self) -> MutexGuard<'_, HashMap<FileId, FileEntry>> {
+        self.files.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
     /// Helper to handle file requests.
     fn get_file(&self, id: FileId) -> FileResult<FileEntry> {
-        let mut files = self.files.lock().map_err(|_| FileError::AccessDenied)?;
-        if let Some(entry) = files.get(&id) {
+        if let Some(entry) = self.lock_files().get(&id) {
             return Ok(entry.clone());
         }
+
+        // the lock isn't held while resolving and reading the file, since preparing a package may
+        // download it, which would otherwise block every other file lookup
         let path = if let Some(package) = id.package() {
-            Err(typst::diag::PackageError::NotFound(package.clone()))?
+            #[cfg(feature = "packages")]
+            if let Some(packages) = &self.packages {
+                let root = packages.prepare(package)?;
+                id.vpath().resolve(&root)
+            } else {
+                Err(PackageError::NotFound(package.clone()))?
+            }
+            #[cfg(not(feature = "packages"))]
+            Err(PackageError::NotFound(package.clone()))?
         } else {
-            id.vpath().resolve(&std::env::current_dir().unwrap())
+            let root = std::env::current_dir()
+                .map_err(|error| FileError::from_io(error, Path::new(".")))?;
+            id.vpath().resolve(&root)
         }
         .ok_or(FileError::AccessDenied)?;
 
         let content = std::fs::read(&path).map_err(|error| FileError::from_io(error, &path))?;
-        Ok(files
+        Ok(self
+            .lock_files()
             .entry(id)
             .or_insert(FileEntry::new(content, None))
             .clone())
@@ -109,7 +299,7 @@ impl typst::World for TypstWrapperWorld {
 
     /// Metadata about all known Books.
     fn book(&self) -> &LazyHash<FontBook> {
-        &FONTS.book
+        self.book.as_ref().unwrap_or(&self.fonts.book)
     }
 
     /// Accessing the main source file.
@@ -133,7 +323,10 @@ impl typst::World for TypstWrapperWorld {
 
     /// Accessing a specified font per index of font book.
     fn font(&self, id: usize) -> Option<Font> {
-        FONTS.fonts[id].get()
+        match self.fonts.fonts.get(id) {
+            Some(slot) => slot.get(),
+            None => self.extra_fonts.get(id - self.fonts.fonts.len()).cloned(),
+        }
     }
 
     /// Get the current date.
@@ -1,12 +1,15 @@
 use std::{
+    cell::RefCell,
+    collections::BTreeMap,
     marker::PhantomData,
     ops::{Deref, DerefMut},
-    path::PathBuf,
+    path::{Path, PathBuf},
     str::FromStr,
 };
 
 use miette::NamedSource;
 use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use sha2::{Digest, Sha256};
 
 use crate::ConfigReadError;
 
@@ -17,11 +20,73 @@ pub struct Deser;
 #[non_exhaustive]
 pub struct Raw;
 
-#[derive(Serialize, Debug, Clone, PartialEq, Eq, Ord, PartialOrd, Hash, Default)]
-pub struct RawOrImport<T, Mode = Deser>(T, PhantomData<Mode>)
+/// A value which is either given inline or imported from another file
+///
+/// Comparisons, hashing, and serialisation only consider the value, not where it came from.
+#[derive(Debug, Clone, Default)]
+pub struct RawOrImport<T, Mode = Deser>(T, Option<PathBuf>, PhantomData<Mode>)
 where
     Mode: Sized;
 
+impl<T: Serialize, Mode> Serialize for RawOrImport<T, Mode> {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        self.0.serialize(serializer)
+    }
+}
+
+impl<T: PartialEq, Mode> PartialEq for RawOrImport<T, Mode> {
+    fn eq(&self, other: &Self) -> bool {
+        self.0 == other.0
+    }
+}
+
+impl<T: Eq, Mode> Eq for RawOrImport<T, Mode> {}
+
+impl<T: PartialOrd, Mode> PartialOrd for RawOrImport<T, Mode> {
+    fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
+        self.0.partial_cmp(&other.0)
+    }
+}
+
+impl<T: Ord, Mode> Ord for RawOrImport<T, Mode> {
+    fn cmp(&self, other: &Self) -> std::cmp::Ordering {
+        self.0.cmp(&other.0)
+    }
+}
+
+impl<T: std::hash::Hash, Mode> std::hash::Hash for RawOrImport<T, Mode> {
+    fn hash<H: std::hash::Hasher>(&self, state: &mut H) {
+        self.0.hash(state)
+    }
+}
+
+/// Where a [`RawOrImport`] value came from
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum Origin {
+    /// The value was given inline in the file that contains it
+    Inline,
+    /// The value was imported from the file at this path
+    Imported(PathBuf),
+}
+
+impl<T, Mode> RawOrImport<T, Mode> {
+    /// The path from which this value was imported, or `None` if it was given inline
+    pub(crate) fn import_path(&self) -> Option<&Path> {
+        self.1.as_deref()
+    }
+
+    /// Whether this value was given inline or imported, and if so, from where
+    pub fn origin(&self) -> Origin {
+        match &self.1 {
+            Some(path) => Origin::Imported(path.clone()),
+            None => Origin::Inline,
+        }
+    }
+}
+
 impl<'de, T> Deserialize<'de> for RawOrImport<T, Deser>
 where
     T: DeserializeOwned,
@@ -38,26 +103,141 @@ where
         let de = serde::__private::de::ContentRefDeserializer::<D::Error>::new(&content);
 
         if let Ok(import) = Import::deserialize(de) {
-            // TODO: Figure out how to make the path relative to the toml file rather than the
-            // runtime
-            // TODO: This sync code makes me want to die
-            let content =
-                std::fs::read_to_string(&import.import).map_err(serde::de::Error::custom)?;
-
-            let x: T = toml_edit::de::from_str(&content)
-                .map_err(|e| {
-                    ConfigReadError::malformed(
-                        NamedSource::new(import.import.display().to_string(), content),
-                        e,
-                    )
-                })
-                .map_err(serde::de::Error::custom)?;
-            return Ok(Self(x, PhantomData));
+            let content = import.read().map_err(serde::de::Error::custom)?;
+            let x = parse_imported(&import, content).map_err(serde::de::Error::custom)?;
+            return Ok(Self(x, Some(import.import), PhantomData));
         }
-        Ok(Self(T::deserialize(de)?, PhantomData))
+        Ok(Self(T::deserialize(de)?, None, PhantomData))
     }
 }
 
+thread_local! {
+    /// The error from the innermost imported file that failed to parse
+    ///
+    /// Serde can only pass a message up to the file that did the importing, so this keeps the
+    /// full error so that it can be reported against the imported file instead.
+    static IMPORT_ERROR: RefCell<Option<ConfigReadError>> = const { RefCell::new(None) };
+
+    /// The directories containing the config file and the imported files that are being parsed,
+    /// innermost last, see [`relative_to_file`]
+    static DIRS: RefCell<Vec<PathBuf>> = const { RefCell::new(Vec::new()) };
+}
+
+/// Resolve `path` relative to the directory containing the file that is being parsed
+///
+/// This is the config file (see [`Config::resolve_path`](crate::Config::resolve_path)), or the
+/// imported file in which `path` was written.  Outside of [`deserialize_with_imports`], `path`
+/// is returned unchanged.
+pub(crate) fn relative_to_file(path: &Path) -> PathBuf {
+    DIRS.with(|dirs| match dirs.borrow().last() {
+        Some(dir) => dir.join(path),
+        None => path.to_path_buf(),
+    })
+}
+
+/// Deserialise a config in `dir` with `deserialize`, keeping track of imported files that fail
+/// to parse
+///
+/// Paths given in the config are relative to `dir` while deserialising, see [`relative_to_file`].
+///
+/// If deserialising fails because of a malformed imported file, the error for that file is
+/// returned alongside the deserialiser's error, so that it can be reported against the imported
+/// file instead.  Errors from imports are forgotten before and after deserialising, and are only
+/// returned if the deserialiser's error came from them, so an error from an earlier parse (or one
+/// that serde recovered from) is never reported for an unrelated failure.
+pub(crate) fn deserialize_with_imports<T, E>(
+    dir: &Path,
+    deserialize: impl FnOnce() -> Result<T, E>,
+) -> Result<T, (E, Option<ConfigReadError>)>
+where
+    E: std::fmt::Display,
+{
+    IMPORT_ERROR.with(|e| e.borrow_mut().take());
+    let outer = DIRS.with(|dirs| dirs.replace(vec![dir.to_path_buf()]));
+    let result = deserialize();
+    DIRS.with(|dirs| dirs.replace(outer));
+    let import_error = IMPORT_ERROR.with(|e| e.borrow_mut().take());
+    result.map_err(|err| {
+        let message = err.to_string();
+        let import_error = import_error.filter(|i| message.contains(&i.to_string()));
+        (err, import_error)
+    })
+}
+
+/// Parse the `content` of an imported file as TOML
+///
+/// If it is malformed, the error is kept for [`deserialize_with_imports`].  Paths inside it are
+/// relative to the imported file, see [`relative_to_file`].
+fn parse_imported<T>(import: &Import, content: String) -> Result<T, String>
+where
+    T: DeserializeOwned,
+{
+    let dir = import.import.parent().unwrap_or(Path::new(""));
+    DIRS.with(|dirs| dirs.borrow_mut().push(dir.to_path_buf()));
+    let result = toml_edit::de::from_str(&content);
+    DIRS.with(|dirs| dirs.borrow_mut().pop());
+    result.map_err(|e| {
+        let err = ConfigReadError::malformed(
+            NamedSource::new(import.import.display().to_string(), content).with_language("TOML"),
+            e,
+        );
+        let message = err.to_string();
+        IMPORT_ERROR.with(|e| {
+            // an import inside the file that failed is closer to the problem
+            e.borrow_mut().get_or_insert(err);
+        });
+        message
+    })
+}
+
+/// Deserialize a list of values, each of which is either given inline or imported from another
+/// file
+///
+/// An imported file which contains nothing but an array named `key` (e.g., `[[problems]]`) is
+/// flattened into the list, so that many values can be imported at once.  Each of those values
+/// is considered to be imported from that file.
+pub(crate) fn deserialize_flattened<'de, D, T>(
+    deserializer: D,
+    key: &str,
+) -> Result<Vec<RawOrImport<T>>, D::Error>
+where
+    D: serde::Deserializer<'de>,
+    T: DeserializeOwned,
+{
+    // see `RawOrImport::deserialize`
+    let entries = Vec::<serde::__private::de::Content>::deserialize(deserializer)?;
+    let mut values = Vec::with_capacity(entries.len());
+    for entry in &entries {
+        let de = serde::__private::de::ContentRefDeserializer::<D::Error>::new(entry);
+        let Ok(import) = Import::deserialize(de) else {
+            values.push(RawOrImport::deserialize(de)?);
+            continue;
+        };
+
+        let content = import.read().map_err(serde::de::Error::custom)?;
+        let is_list = content.parse::<toml_edit::DocumentMut>().is_ok_and(|doc| {
+            doc.len() == 1
+                && doc
+                    .get(key)
+                    .is_some_and(|v| v.is_array_of_tables() || v.is_array())
+        });
+        if is_list {
+            let mut list: BTreeMap<String, Vec<T>> =
+                parse_imported(&import, content).map_err(serde::de::Error::custom)?;
+            values.extend(
+                list.remove(key)
+                    .unwrap_or_default()
+                    .into_iter()
+                    .map(|x| RawOrImport(x, Some(import.import.clone()), PhantomData)),
+            );
+        } else {
+            let x = parse_imported(&import, content).map_err(serde::de::Error::custom)?;
+            values.push(RawOrImport(x, Some(import.import), PhantomData));
+        }
+    }
+    Ok(values)
+}
+
 impl<'de, S> Deserialize<'de> for RawOrImport<S, Raw>
 where
     S: FromStr + Deserialize<'de>,
@@ -75,18 +255,15 @@ where
         let de = serde::__private::de::ContentRefDeserializer::<D::Error>::new(&content);
 
         if let Ok(import) = Import::deserialize(de) {
-            // TODO: Figure out how to make the path relative to the toml file rather than the
-            // runtime
-            // TODO: This sync code makes me want to die
-            let content =
-                std::fs::read_to_string(&import.import).map_err(serde::de::Error::custom)?;
+            let content = import.read().map_err(serde::de::Error::custom)?;
 
             return Ok(Self(
                 content.parse().map_err(serde::de::Error::custom)?,
+                Some(import.import),
                 PhantomData,
             ));
         }
-        Ok(Self(S::deserialize(de)?, PhantomData))
+        Ok(Self(S::deserialize(de)?, None, PhantomData))
     }
 }
 
@@ -94,6 +271,41 @@ where
 #[serde(deny_unknown_fields)]
 struct Import {
     import: PathBuf,
+    /// Expected SHA-256 of the imported file, as hex
+    sha256: Option<String>,
+}
+
+impl Import {
+    /// Read the imported file, checking that it matches [`Import::sha256`] if one was given
+    fn read(&self) -> Result<String, String> {
+        // TODO: Figure out how to make the path relative to the toml file rather than the
+        // runtime
+        // TODO: This sync code makes me want to die
+        let content = std::fs::read_to_string(&self.import)
+            .map_err(|e| format!("Failed to read {}: {}", self.import.display(), e))?;
+
+        if let Some(expected) = &self.sha256 {
+            let actual = Sha256::digest(content.as_bytes())
+                .iter()
+                .map(|b| format!("{:02x}", b))
+                .collect::<String>();
+            if !actual.eq_ignore_ascii_case(expected.trim()) {
+                return Err(format!(
+                    "Checksum mismatch for {}: expected sha256 {}, found {}",
+                    self.import.display(),
+                    expected,
+                    actual
+                ));
+            }
+        }
+
+        // editors on Windows like to add a byte order mark, which would otherwise end up in the
+        // imported value (e.g., as a stray character at the start of a markdown file)
+        Ok(match content.strip_prefix('\u{feff}') {
+            Some(content) => content.to_string(),
+            None => content,
+        })
+    }
 }
 
 impl<T, Mode> Deref for RawOrImport<T, Mode> {
@@ -112,6 +324,6 @@ impl<T, Mode> DerefMut for RawOrImport<T, Mode> {
 
 impl<T, Mode> From<T> for RawOrImport<T, Mode> {
     fn from(value: T) -> Self {
-        Self(value, PhantomData)
+        Self(value, None, PhantomData)
     }
 }
@@ -0,0 +1,40 @@
+use crate::Config;
+
+/// An overview of a [`Config`], e.g., for a command that prints information about it
+///
+/// See [`Config::summary`].
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Default)]
+pub struct ConfigSummary {
+    /// Title of the packet
+    pub title: String,
+    /// The number of problems in the packet
+    pub problem_count: usize,
+    /// The number of tests across every problem, including hidden tests
+    pub test_count: usize,
+    /// The number of tests across every problem which are shown to competitors
+    pub visible_test_count: usize,
+    /// The number of languages that solutions may be written in
+    pub language_count: usize,
+    /// The number of admin accounts
+    pub admin_count: usize,
+    /// The number of competitor accounts
+    pub competitor_count: usize,
+    /// Port on which the server will be hosted
+    pub port: u16,
+}
+
+impl Config {
+    /// Count the things in this config, along with some of its settings
+    pub fn summary(&self) -> ConfigSummary {
+        ConfigSummary {
+            title: self.packet.title.clone(),
+            problem_count: self.packet.problem_count(),
+            test_count: self.packet.test_count(),
+            visible_test_count: self.packet.visible_test_count(),
+            language_count: self.languages.len(),
+            admin_count: self.accounts.admins.len(),
+            competitor_count: self.accounts.competitors.len(),
+            port: self.port,
+        }
+    }
+}
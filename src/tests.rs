@@ -1,5 +1,20 @@
-use language::{BuiltInLanguage, Language, Version};
+use language::{BuiltInLanguage, Language, LanguageSet, Version};
 use miette::Result;
+use render::{
+    markdown::{
+        code_language, render_markdown, render_markdown_with, HtmlMode, MarkdownOptions,
+        MarkdownRenderable, TableWidths,
+    },
+    typst::{FontConfig, TypstWrapperWorld},
+};
+use strum::VariantNames;
+use typst::{
+    foundations::{NativeElement, Selector, Smart, StyleChain, Value},
+    layout::{Abs, BlockElem, Sizing},
+    model::{EnumItem, FigureElem, TableChild, TableElem, TermsElem},
+    text::RawElem,
+    visualize::LineElem,
+};
 
 use super::*;
 use std::io::Cursor;
@@ -40,7 +55,8 @@ fn packet_files_parse_correctly() -> Result<()> {
             name: "ocaml".into(),
             build: Some("ocamlc -o out solution.ml".into()),
             run: "./out".into(),
-            source_file: "solution.ml".into()
+            source_file: "solution.ml".into(),
+            init: None,
         }),
         config.languages.get_by_str("ocaml")
     );
@@ -58,8 +74,3302 @@ async fn packet_files_parse_correctly_async() -> Result<()> {
     Ok(())
 }
 
+#[tokio::test]
+async fn render_pdf_async() -> Result<()> {
+    let config = Config::from_str(EXAMPLE_ONE_CONTENT, Some("one.toml"))?;
+    let pdf = config.render_pdf_async(None).await.unwrap();
+    assert!(!pdf.is_empty());
+    Ok(())
+}
+
+#[test]
+fn hash_options() {
+    assert_eq!(
+        util::encode_hash(0),
+        util::encode_hash_with(0, &HashOptions::default())
+    );
+    assert_eq!("a", util::encode_hash(0));
+
+    let fixed = HashOptions::default().with_min_length(14);
+    assert_eq!("baaaaaaaaaaaaa", util::encode_hash_with(1, &fixed));
+    assert_eq!(14, util::encode_hash_with(u64::MAX, &fixed).len());
+
+    let binary = HashOptions::default().with_alphabet("01").unwrap();
+    assert_eq!("0101", util::encode_hash_with(10, &binary));
+    assert_eq!(64, util::encode_hash_with(u64::MAX, &binary).len());
+
+    assert!(HashOptions::default().with_alphabet("x").is_none());
+    assert!(HashOptions::default().with_alphabet("abca").is_none());
+
+    let config = Config::from_str(EXAMPLE_ONE_CONTENT, Some("one.toml")).unwrap();
+    assert_eq!(config.hash(), config.hash_with(&HashOptions::default()));
+    let url_safe = HashOptions::default()
+        .with_alphabet(HashOptions::URL_SAFE)
+        .unwrap()
+        .with_min_length(12);
+    let hash = config.hash_with(&url_safe);
+    assert_eq!(hash, config.hash_with(&url_safe));
+    assert!(hash.len() >= 12);
+    assert!(hash
+        .chars()
+        .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_'));
+}
+
+#[test]
+fn competitor_view_strips_private_data() -> Result<()> {
+    let config = Config::from_str(
+        r#"
+[setup]
+install = "dnf install secret-sauce"
+
+[languages]
+python3 = "latest"
+ocaml = { run = "ocaml solution.ml", source_file = "solution.ml", init = "opam install secret-library" }
+
+[[accounts.admins]]
+name = "Teacher"
+password = "admin-password"
+
+[[accounts.competitors]]
+name = "StudentOne"
+password = "student-password"
+
+[packet]
+title = "Competitor view"
+
+[[packet.problems]]
+title = "Reverse"
+
+[[packet.problems.tests]]
+input = "visible-input"
+output = "visible-output"
+visible = true
+
+[[packet.problems.tests]]
+input = "hidden-input"
+output = "hidden-output"
+"#,
+        Some("competitor.toml"),
+    )?;
+
+    let view = config.competitor_view();
+    assert_eq!(vec!["StudentOne".to_string()], view.competitors);
+    assert_eq!(1, view.packet.problem(0).unwrap().tests.len());
+
+    let json = serde_json::to_string(&view).unwrap();
+    for secret in [
+        "Teacher",
+        "admin-password",
+        "student-password",
+        "hidden-input",
+        "hidden-output",
+        "secret-sauce",
+        "secret-library",
+    ] {
+        assert!(!json.contains(secret), "{} was leaked", secret);
+    }
+    assert!(json.contains("visible-input"));
+    assert!(matches!(
+        view.languages.get_by_str("ocaml"),
+        Some(Language::Custom { init: None, .. })
+    ));
+    assert_eq!(view, serde_json::from_str(&json).unwrap());
+    Ok(())
+}
+
+#[test]
+fn find_user() {
+    let user = |name: &str| User {
+        name: name.into(),
+        password: format!("{}-password", name),
+    };
+    let config = Config {
+        accounts: Accounts {
+            admins: vec![user("host")],
+            competitors: vec![user("alice"), user("bob")],
+        }
+        .into(),
+        ..Default::default()
+    };
+
+    assert_eq!(Some((Role::Admin, &user("host"))), config.find_user("host"));
+    assert_eq!(
+        Some((Role::Competitor, &user("bob"))),
+        config.find_user("bob")
+    );
+    assert_eq!(None, config.find_user("mallory"));
+    assert_eq!(
+        vec![Role::Admin, Role::Competitor, Role::Competitor],
+        config
+            .accounts
+            .iter_with_roles()
+            .map(|(role, _)| role)
+            .collect::<Vec<_>>()
+    );
+}
+
 #[test]
 fn default_config() {
     let config = Config::default();
     dbg!(config.hash());
 }
+
+#[test]
+fn language_command_configs() {
+    let python = Language::BuiltIn {
+        language: BuiltInLanguage::Python3,
+        version: Version::Latest,
+    };
+    assert_eq!(
+        CommandConfig::Run {
+            run: "python3 ./solution.py".into()
+        },
+        python.to_command_config()
+    );
+
+    let rust = Language::BuiltIn {
+        language: BuiltInLanguage::Rust,
+        version: Version::Latest,
+    };
+    assert_eq!(
+        CommandConfig::Each {
+            compile: "rustc -o solution solution.rs".into(),
+            run: "./solution".into(),
+        },
+        rust.to_command_config()
+    );
+}
+
+#[test]
+fn language_set_builders() {
+    let languages = LanguageSet::new()
+        .with_builtin(BuiltInLanguage::Python3, Version::Latest)
+        .unwrap()
+        .with_custom(
+            "ocaml",
+            Some("ocamlc -o out solution.ml"),
+            "./out",
+            "solution.ml",
+        );
+    assert_eq!(
+        r#"python3 = "latest"
+
+[ocaml]
+name = "ocaml"
+build = "ocamlc -o out solution.ml"
+run = "./out"
+source_file = "solution.ml"
+"#,
+        toml_edit::ser::to_string_pretty(&languages).unwrap()
+    );
+}
+
+#[test]
+fn language_set_builder_checks_version() {
+    let err = LanguageSet::new()
+        .with_builtin(BuiltInLanguage::Java, Version::Specific("9".into()))
+        .unwrap_err();
+    assert_eq!(BuiltInLanguage::Java, err.language);
+    assert_eq!("9", err.version);
+    assert!(err.known.contains(&"21".to_string()));
+    assert!(err.to_string().starts_with("Unknown java version: '9'"));
+}
+
+#[test]
+fn language_extensions() {
+    let python = Language::BuiltIn {
+        language: BuiltInLanguage::Python3,
+        version: Version::Latest,
+    };
+    assert_eq!(Some("py"), python.extension());
+
+    let java = Language::BuiltIn {
+        language: BuiltInLanguage::Java,
+        version: Version::Specific("21".into()),
+    };
+    assert_eq!(Some("java"), java.extension());
+
+    let ocaml = Language::Custom {
+        raw_name: "ocaml".into(),
+        name: "ocaml".into(),
+        build: Some("ocamlc -o out solution.ml".into()),
+        run: "./out".into(),
+        source_file: "solution.ml".into(),
+        init: None,
+    };
+    assert_eq!(Some("ml"), ocaml.extension());
+
+    let no_ext = Language::Custom {
+        raw_name: "bash".into(),
+        name: "bash".into(),
+        build: None,
+        run: "sh ./solution".into(),
+        source_file: "solution".into(),
+        init: None,
+    };
+    assert_eq!(None, no_ext.extension());
+}
+
+#[test]
+fn language_timeout_overrides() -> Result<()> {
+    let config = Config::from_str(
+        r#"
+[languages]
+python3 = "latest"
+java = "21"
+
+[accounts]
+admins = []
+competitors = []
+
+[packet]
+title = "Overrides"
+problems = []
+
+[test_runner]
+timeout_ms = 5_000
+
+[test_runner.language_overrides.java]
+timeout_ms = { compile = 30_000 }
+"#,
+        Some("overrides.toml"),
+    )?;
+    config.validate().unwrap();
+
+    let runner = &config.test_runner;
+    assert_eq!(
+        Duration::from_secs(30),
+        runner.timeout_for("java", Phase::Compile)
+    );
+    assert_eq!(
+        Duration::from_secs(5),
+        runner.timeout_for("java", Phase::Run)
+    );
+    assert_eq!(
+        Duration::from_secs(5),
+        runner.timeout_for("python3", Phase::Compile)
+    );
+    Ok(())
+}
+
+#[test]
+fn checker_language_validation() -> Result<()> {
+    let config = |checker_language: &str| {
+        Config::from_str(
+            format!(
+                r#"
+[languages]
+python3 = "latest"
+
+[accounts]
+admins = []
+competitors = []
+
+[packet]
+title = "Checkers"
+
+[[packet.problems]]
+title = "Any Order"
+checker = "import sys; sys.exit(0)"
+{}
+tests = [{{ input = "1 2", output = "2 1", visible = true }}]
+"#,
+                checker_language
+            ),
+            Some("checkers.toml"),
+        )
+    };
+
+    let valid = config(r#"checker_language = "python3""#)?;
+    let problem = &valid.packet.problems[0];
+    assert_eq!(
+        Some("import sys; sys.exit(0)"),
+        problem.checker.as_deref().map(String::as_str)
+    );
+    assert_eq!(Ok(()), valid.validate());
+    assert_eq!(None, valid.competitor_view().packet.problems[0].checker);
+
+    assert_eq!(
+        Err(vec![validate::ValidationError::UnknownCheckerLanguage {
+            problem: "Any Order".into(),
+            language: "rust".into(),
+        }]),
+        config(r#"checker_language = "rust""#)?.validate()
+    );
+    assert_eq!(
+        Err(vec![validate::ValidationError::MissingCheckerLanguage(
+            "Any Order".into()
+        )]),
+        config("")?.validate()
+    );
+    Ok(())
+}
+
+#[test]
+fn effective_languages() -> Result<()> {
+    let config = Config::from_str(
+        r#"
+[languages]
+python3 = "latest"
+java = "21"
+rust = "latest"
+
+[accounts]
+admins = []
+competitors = []
+
+[packet]
+title = "Languages"
+
+[[packet.problems]]
+title = "Anything"
+tests = []
+
+[[packet.problems]]
+title = "Restricted"
+languages = ["rust", "python3", "cobol"]
+tests = []
+"#,
+        Some("effective.toml"),
+    )?;
+    let names = |problem: usize| {
+        config
+            .effective_languages(&config.packet.problems[problem])
+            .into_iter()
+            .map(Language::raw_name)
+            .collect::<Vec<_>>()
+    };
+    assert_eq!(vec!["java", "python3", "rust"], names(0));
+    assert_eq!(vec!["python3", "rust"], names(1));
+    assert_eq!(
+        Err(vec![validate::ValidationError::UnknownProblemLanguage {
+            problem: "Restricted".into(),
+            language: "cobol".into(),
+        }]),
+        config.validate()
+    );
+    Ok(())
+}
+
+#[test]
+fn language_timeout_override_unknown_language() -> Result<()> {
+    let config = Config::from_str(
+        r#"
+[languages]
+python3 = "latest"
+
+[accounts]
+admins = []
+competitors = []
+
+[packet]
+title = "Overrides"
+problems = []
+
+[test_runner.language_overrides.rust]
+timeout_ms = 20_000
+"#,
+        Some("overrides.toml"),
+    )?;
+    assert_eq!(
+        Err(vec![validate::ValidationError::UnknownOverrideLanguage(
+            "rust".into()
+        )]),
+        config.validate()
+    );
+    Ok(())
+}
+
+#[test]
+fn install_script() -> Result<()> {
+    let config = Config::from_str(
+        r#"
+[setup]
+install = "dnf install opam"
+
+[languages]
+python3 = "latest"
+java = "21"
+ocaml = { build = "ocamlc -o out solution.ml", run = "./out", source_file = "solution.ml" }
+
+[accounts]
+admins = []
+competitors = []
+
+[packet]
+title = "Install"
+problems = []
+"#,
+        Some("install.toml"),
+    )?;
+
+    let script = config.install_script();
+    assert_eq!(1, script.matches("dnf install python3").count());
+    assert_eq!(
+        1,
+        script.matches("dnf install java-21-openjdk-devel").count()
+    );
+    assert!(script.ends_with("dnf install opam"));
+    Ok(())
+}
+
+#[test]
+fn install_script_from_list() -> Result<()> {
+    let config = Config::from_str(
+        r#"
+[setup]
+install = ["dnf install python3-pip", "pip install numpy"]
+init = "echo ready"
+
+[languages]
+python3 = "latest"
+
+[accounts]
+admins = []
+competitors = []
+
+[packet]
+title = "Install"
+problems = []
+"#,
+        Some("install.toml"),
+    )?;
+
+    let script = config.install_script();
+    assert!(script.ends_with("dnf install python3-pip\npip install numpy"));
+    assert_eq!("echo ready", config.init_script());
+    Ok(())
+}
+
+#[test]
+fn version_serializes_as_string() {
+    let cases = [
+        (Version::Latest, "\"latest\""),
+        (Version::Specific("21".into()), "\"21\""),
+    ];
+    for (version, json) in cases {
+        assert_eq!(json, serde_json::to_string(&version).unwrap());
+        assert_eq!(version, serde_json::from_str::<Version>(json).unwrap());
+    }
+    assert_eq!(Version::Latest, serde_json::from_str("\"*\"").unwrap());
+}
+
+#[test]
+fn language_command_summary() {
+    let python = Language::BuiltIn {
+        language: BuiltInLanguage::Python3,
+        version: Version::Latest,
+    };
+    assert_eq!("run: python3 ./solution.py", python.command_summary());
+
+    let java = Language::BuiltIn {
+        language: BuiltInLanguage::Java,
+        version: Version::Specific("21".into()),
+    };
+    assert_eq!(
+        "build: javac Solution.java | run: java Solution",
+        java.command_summary()
+    );
+}
+
+#[test]
+fn init_script() -> Result<()> {
+    let config = Config::from_str(
+        r#"
+[setup]
+init = """
+opam init -y
+eval $(opam env)
+"""
+
+[languages]
+python3 = "latest"
+ocaml = { build = "ocamlc -o out solution.ml", run = "./out", source_file = "solution.ml", init = "opam switch create 5.2.0" }
+ocaml-native = { build = "ocamlopt -o out solution.ml", run = "./out", source_file = "solution.ml", init = "opam switch create 5.2.0" }
+
+[accounts]
+admins = []
+competitors = []
+
+[packet]
+title = "Init"
+problems = []
+"#,
+        Some("init.toml"),
+    )?;
+
+    assert_eq!(
+        "opam switch create 5.2.0\nopam init -y\neval $(opam env)",
+        config.init_script()
+    );
+    Ok(())
+}
+
+#[test]
+fn blockquotes_render_as_blocks() {
+    let world = TypstWrapperWorld::new("");
+    let content = render_markdown("> quote", &world).unwrap();
+    assert!(content
+        .query_first(Selector::Elem(FigureElem::elem(), None))
+        .is_none());
+    let quote = content
+        .query_first(Selector::Elem(BlockElem::elem(), None))
+        .unwrap();
+    assert_eq!("quote", quote.plain_text());
+
+    let content = render_markdown("> [!WARNING]\n> careful", &world).unwrap();
+    let quote = content
+        .query_first(Selector::Elem(BlockElem::elem(), None))
+        .unwrap();
+    assert!(quote.plain_text().starts_with("Warning"));
+}
+
+#[test]
+fn ordered_list_numbers() {
+    let world = TypstWrapperWorld::new("");
+    let numbers = |markdown: &str| {
+        render_markdown(markdown, &world)
+            .unwrap()
+            .query(Selector::Elem(EnumItem::elem(), None))
+            .iter()
+            .map(|item| {
+                item.to_packed::<EnumItem>()
+                    .unwrap()
+                    .number(StyleChain::default())
+            })
+            .collect::<Vec<_>>()
+    };
+
+    assert_eq!(vec![Some(5), Some(6), Some(7)], numbers("5. a\n6. b\n7. c"));
+    assert_eq!(vec![Some(3), Some(4)], numbers("3. a\n1. b"));
+    assert_eq!(
+        vec![Some(5), Some(6), Some(1), Some(2)],
+        numbers("5. a\n6. b\n\ntext\n\n1. c\n2. d")
+    );
+    // changing the delimiter starts a new list
+    assert_eq!(vec![Some(2), Some(1)], numbers("2. a\n1) b"));
+}
+
+#[test]
+fn virtual_files_are_available_to_templates() {
+    use base64::prelude::{Engine as _, BASE64_STANDARD};
+
+    // a 1x1 PNG
+    let png = BASE64_STANDARD
+        .decode("iVBORw0KGgoAAAANSUhEUgAAAAEAAAABCAYAAAAfFcSJAAAADUlEQVR42mNkYPhfDwAChwGA60e6kgAAAABJRU5ErkJggg==")
+        .unwrap();
+    let world = TypstWrapperWorld::new("#image(\"logo.png\", width: 1cm)");
+    assert!(typst::compile(&world).output.is_err());
+
+    world.insert_file("logo.png", png);
+    let document = typst::compile(&world).output.unwrap();
+    assert!(typst_svg::svg(&document.pages[0]).contains("<image"));
+}
+
+#[cfg(feature = "packages")]
+#[test]
+fn templates_can_import_packages() -> Result<()> {
+    let config = Config::from_str(EXAMPLE_ONE_CONTENT, Some("one.toml"))?;
+    let template = Some(String::from(
+        "#import \"@preview/greeting:0.1.0\": greet\n#metadata(greet(title)) <greeting>",
+    ));
+    let options = RenderOptions {
+        packages: Some(render::typst::PackageConfig {
+            package_dir: Some("tests/data/packages".into()),
+            cache_dir: None,
+            allow_downloads: false,
+        }),
+        ..Default::default()
+    };
+    let world = config.typst_world(template.clone(), &options).unwrap();
+    let document = typst::compile(&world).output.unwrap();
+    let greeting = document
+        .introspector
+        .query_label(typst::foundations::Label::construct("greeting".into()))
+        .unwrap()
+        .to_packed::<typst::introspection::MetadataElem>()
+        .unwrap()
+        .value
+        .clone();
+    let Value::Content(greeting) = greeting else {
+        panic!("the greeting should be content");
+    };
+    assert_eq!("Hello, Example Packet!", greeting.plain_text());
+
+    // packages are only available when they are enabled
+    let world = config
+        .typst_world(template, &RenderOptions::default())
+        .unwrap();
+    assert!(typst::compile(&world).output.is_err());
+    Ok(())
+}
+
+#[test]
+fn out_of_range_fonts_and_dates() {
+    use typst::World;
+
+    let world = TypstWrapperWorld::new("");
+    assert!(world.font(0).is_some());
+    assert!(world.font(usize::MAX).is_none());
+    assert!(world.today(Some(i64::MAX)).is_none());
+    assert!(world.today(Some(-1)).is_some());
+}
+
+#[test]
+fn horizontal_rules_are_visible() {
+    let world = TypstWrapperWorld::new("");
+    let content = render_markdown("above\n\n---\n\nbelow", &world).unwrap();
+    let line = content
+        .query_first(Selector::Elem(LineElem::elem(), None))
+        .unwrap();
+    let line = line.to_packed::<LineElem>().unwrap();
+    let styles = StyleChain::default();
+    assert!(line.length(styles).rel.get() > 0.0);
+    let thickness = match line.stroke(styles).thickness {
+        Smart::Custom(thickness) => thickness,
+        Smart::Auto => panic!("the rule should have an explicit thickness"),
+    };
+    assert!(thickness > Abs::zero());
+    assert!(content
+        .query_first(Selector::Elem(BlockElem::elem(), None))
+        .is_some());
+}
+
+#[test]
+fn code_blocks_keep_newlines() {
+    let world = TypstWrapperWorld::new("");
+    let content =
+        render_markdown("```\nfn main() {\n    println!(\"hi\");\n}\n```", &world).unwrap();
+    let raw = content
+        .query_first(Selector::Elem(RawElem::elem(), None))
+        .unwrap();
+    assert_eq!("fn main() {\n    println!(\"hi\");\n}\n", raw.plain_text());
+}
+
+#[test]
+fn inline_code_is_literal() {
+    let world = TypstWrapperWorld::new("");
+    let content = render_markdown("cost `a $ b` and `` `x`  *y* ``", &world).unwrap();
+    assert!(content
+        .query_first(Selector::Elem(typst::math::EquationElem::elem(), None))
+        .is_none());
+    assert!(content
+        .query_first(Selector::Elem(typst::model::StrongElem::elem(), None))
+        .is_none());
+
+    let raws = content.query(Selector::Elem(RawElem::elem(), None));
+    let texts = raws.iter().map(|r| r.plain_text()).collect::<Vec<_>>();
+    assert_eq!(vec!["a $ b", "`x`  *y*"], texts);
+}
+
+fn code_block_lang(markdown: &str) -> Option<Value> {
+    let world = TypstWrapperWorld::new("");
+    let content = render_markdown(markdown, &world).unwrap();
+    let raw = content
+        .query_first(Selector::Elem(RawElem::elem(), None))
+        .unwrap();
+    raw.get_by_name("lang").ok()
+}
+
+#[test]
+fn code_block_languages() {
+    assert_eq!(
+        Some(Value::Str("rust".into())),
+        code_block_lang("```rust\nfn main() {}\n```")
+    );
+    assert_eq!(
+        Some(Value::Str("python".into())),
+        code_block_lang("```py\nprint(1)\n```")
+    );
+    assert_eq!(None, code_block_lang("```not-a-language\nhello\n```"));
+    assert_eq!(None, code_block_lang("```\nhello\n```"));
+    assert_eq!(Some("rust"), code_language("Rust,ignore"));
+}
+
+#[test]
+fn code_block_languages_html() {
+    let html = MarkdownRenderable::from("```py\nprint(1)\n```\n\n```nope\nx\n```")
+        .html()
+        .unwrap();
+    assert!(html.contains(r#"<code class="language-python">"#));
+    assert!(!html.contains("language-nope"));
+}
+
+#[test]
+fn builtin_language_aliases() -> Result<()> {
+    let config = Config::from_str(
+        r#"
+[languages]
+py = "latest"
+js = "latest"
+
+[accounts]
+admins = []
+competitors = []
+
+[packet]
+title = "Aliases"
+problems = []
+"#,
+        Some("aliases.toml"),
+    )?;
+    assert_eq!(
+        Some(&Language::BuiltIn {
+            language: BuiltInLanguage::Python3,
+            version: Version::Latest
+        }),
+        config.languages.get_by_str("python3")
+    );
+    // aliases can be used to refer to languages, e.g., in `Problem::languages`
+    assert_eq!(
+        config.languages.get_by_str("python3"),
+        config.languages.get_by_str("py")
+    );
+    assert!(config.languages.get_by_str("PY").is_some());
+    assert!(config
+        .languages
+        .get_by_str("javascript")
+        .is_some_and(|l| l.raw_name() == "javascript"));
+
+    assert_eq!(Ok(BuiltInLanguage::Rust), "rs".parse());
+    assert_eq!(BuiltInLanguage::JavaScript, BuiltInLanguage::from("node"));
+    let mut aliases = BuiltInLanguage::Python3.aliases().collect::<Vec<_>>();
+    aliases.sort();
+    assert_eq!(vec!["py", "python"], aliases);
+    Ok(())
+}
+
+#[test]
+fn builtin_languages_ignore_case() -> Result<()> {
+    assert_eq!(Ok(BuiltInLanguage::Rust), "Rust".parse());
+    assert_eq!(Ok(BuiltInLanguage::Python3), "PYTHON3".parse());
+    assert_eq!(Ok(BuiltInLanguage::Python3), "Py".parse());
+    assert_eq!("rust", BuiltInLanguage::from("RUST").as_str());
+
+    let config = Config::from_str(
+        r#"
+[languages]
+Rust = "latest"
+PYTHON3 = "latest"
+
+[accounts]
+admins = []
+competitors = []
+
+[packet]
+title = "Capitals"
+problems = []
+"#,
+        Some("capitals.toml"),
+    )?;
+    assert!(config.languages.get_by_str("rust").is_some());
+    assert!(config.languages.get_by_str("python3").is_some());
+    Ok(())
+}
+
+#[test]
+fn sort_problems_by_order() -> Result<()> {
+    let config = Config::from_str(
+        r#"
+[languages]
+python3 = "latest"
+
+[accounts]
+admins = []
+competitors = []
+
+[packet]
+title = "Ordered"
+sort_by_order = true
+
+[[packet.problems]]
+title = "Second"
+order = 2
+tests = []
+
+[[packet.problems]]
+title = "Unordered"
+tests = []
+
+[[packet.problems]]
+title = "First"
+order = 1
+tests = []
+"#,
+        Some("ordered.toml"),
+    )?;
+
+    let titles = config
+        .packet
+        .ordered_problems()
+        .into_iter()
+        .map(|p| p.title.as_str())
+        .collect::<Vec<_>>();
+    assert_eq!(vec!["First", "Second", "Unordered"], titles);
+
+    let html = config.render_html().unwrap().html;
+    let position = |title: &str| html.find(&format!(">{}<", title)).unwrap();
+    assert!(position("First") < position("Second"));
+    assert!(position("Second") < position("Unordered"));
+    Ok(())
+}
+
+#[test]
+fn sort_problems_by_difficulty() {
+    let problem = |title: &str, difficulty| {
+        RawOrImport::from(packet::Problem {
+            title: title.into(),
+            difficulty,
+            ..Default::default()
+        })
+    };
+    let packet = packet::Packet {
+        problems: vec![
+            problem("Three", Some(3)),
+            problem("Unrated", None),
+            problem("One", Some(1)),
+            problem("Also three", Some(3)),
+        ],
+        ..Default::default()
+    };
+    let titles = packet
+        .sorted_by_difficulty()
+        .into_iter()
+        .map(|p| p.title.as_str())
+        .collect::<Vec<_>>();
+    assert_eq!(vec!["One", "Three", "Also three", "Unrated"], titles);
+}
+
+#[test]
+fn html_images() {
+    let html = |markdown: &str| MarkdownRenderable::from(markdown).html().unwrap();
+
+    let data = "data:image/gif;base64,R0lGODlhAQABAAAAACw=";
+    assert!(html(&format!("![a pixel]({})", data))
+        .contains(&format!(r#"<img src="{}" alt="a pixel" />"#, data)));
+
+    // local files are only read from an explicit directory
+    assert_eq!("<p>a dot</p>\n", html("![a dot](./tests/data/dot.svg)"));
+    let with_dir = |markdown: &str, dir: &str| {
+        MarkdownRenderable::from(markdown)
+            .html_with_options(&MarkdownOptions {
+                image_dir: Some(dir.into()),
+                ..Default::default()
+            })
+            .unwrap()
+    };
+    let local = with_dir("![a dot](./dot.svg)", "./tests/data");
+    assert!(local.contains(r#"<img src="data:image/svg+xml;base64,"#));
+    assert!(local.contains(r#"alt="a dot""#));
+    assert_eq!(
+        "<p>a dot</p>\n",
+        with_dir("![a dot](../dot.svg)", "./tests/data/fonts")
+    );
+    let absolute = std::fs::canonicalize("./tests/data/dot.svg").unwrap();
+    assert_eq!(
+        "<p>a dot</p>\n",
+        with_dir(&format!("![a dot]({})", absolute.display()), "./tests/data")
+    );
+
+    let unsupported = html("![a secret](ftp://example.com/secret.png)");
+    assert_eq!("<p>a secret</p>\n", unsupported);
+    let missing = html("![missing](./tests/data/missing.png)");
+    assert_eq!("<p>missing</p>\n", missing);
+}
+
+const HTML_DESCRIPTION: &str = "Some text\n\n<div>\nhello\n</div>\n";
+
+#[test]
+fn html_is_an_error_by_default() {
+    let world = TypstWrapperWorld::new("");
+    let err = render_markdown(HTML_DESCRIPTION, &world).unwrap_err();
+    assert_eq!(Some(11..30), err.span());
+    assert_eq!(
+        "<div>\nhello\n</div>\n",
+        &HTML_DESCRIPTION[err.span().unwrap()]
+    );
+}
+
+#[test]
+fn validate_markdown() {
+    assert_eq!(
+        Ok(()),
+        MarkdownRenderable::from("Some $x^2$ maths").validate()
+    );
+
+    let markdown = format!(
+        "{}\nBroken $\\frac$ maths and a <b>tag</b>",
+        HTML_DESCRIPTION
+    );
+    let errors = MarkdownRenderable::from(markdown.as_str())
+        .validate()
+        .unwrap_err();
+    let spans = errors
+        .iter()
+        .map(|e| &markdown[e.span().unwrap()])
+        .collect::<Vec<_>>();
+    assert_eq!(
+        vec!["<div>\nhello\n</div>\n", "$\\frac$", "<b>", "</b>"],
+        spans
+    );
+    assert!(matches!(
+        errors[1],
+        render::markdown::RenderError::MathError { .. }
+    ));
+}
+
+#[test]
+fn images_are_not_valid_in_pdfs() {
+    let markdown = "An image: ![x](y.png)";
+    let errors = MarkdownRenderable::from(markdown).validate().unwrap_err();
+    assert!(matches!(
+        errors[..],
+        [render::markdown::RenderError::UnsupportedImage { .. }]
+    ));
+    assert_eq!("![x](y.png)", &markdown[errors[0].span().unwrap()]);
+
+    let world = TypstWrapperWorld::new("");
+    assert!(matches!(
+        render::markdown::render_markdown(markdown, &world),
+        Err(render::markdown::RenderError::UnsupportedImage { .. })
+    ));
+}
+
+#[test]
+fn math_expressions_are_listed() {
+    let markdown = MarkdownRenderable::from_raw("Let $x + 1$ be\n\n$$\nsum_(i=0)^n i\n$$\n");
+    assert_eq!(
+        vec![
+            render::markdown::MathExpr {
+                source: "x + 1".into(),
+                display: false,
+            },
+            render::markdown::MathExpr {
+                source: "\nsum_(i=0)^n i\n".into(),
+                display: true,
+            },
+        ],
+        markdown.math_expressions()
+    );
+}
+
+#[test]
+fn unknown_code_languages_warn_in_strict_mode() {
+    let markdown =
+        MarkdownRenderable::from_raw("```pyton\nprint(1)\n```\n\n```python\nprint(2)\n```");
+    let lenient = markdown.html().unwrap();
+    let (strict, warnings) = markdown.html_strict().unwrap();
+    assert_eq!(lenient, strict);
+    assert_eq!(
+        vec![render::markdown::MarkdownWarning::UnknownCodeLanguage {
+            span: 0..21,
+            language: "pyton".into(),
+        }],
+        warnings
+    );
+}
+
+#[test]
+fn word_count_ignores_code_and_maths() {
+    let markdown = MarkdownRenderable::from_raw(
+        "# Reversing\n\nPrint the *rev*ersed string `s`, which has length $n$.\n\n```python\nprint(input()[::-1])\n```\n\n- one item",
+    );
+    // Reversing, Print the reversed string, which has length, one item
+    assert_eq!(10, markdown.word_count());
+    assert_eq!(std::time::Duration::from_secs(3), markdown.reading_time());
+    assert_eq!(0, MarkdownRenderable::default().word_count());
+}
+
+#[test]
+fn html_can_be_stripped() {
+    let world = TypstWrapperWorld::new("");
+    let content = render_markdown_with(HTML_DESCRIPTION, &world, HtmlMode::Strip).unwrap();
+    assert_eq!("Some text", content.plain_text().trim());
+}
+
+#[test]
+fn tables_can_fit_the_page() {
+    let world = TypstWrapperWorld::new("");
+    let table = "| a | b | c | d |\n|---|---|---|---|\n| 1 | a longer cell | 3 | 4 |";
+    let columns = |options: MarkdownOptions| {
+        let content = render_markdown_with(table, &world, options).unwrap();
+        let table = content
+            .query_first(Selector::Elem(TableElem::elem(), None))
+            .unwrap();
+        let table = table.to_packed::<TableElem>().unwrap();
+        table.columns(StyleChain::default()).0.to_vec()
+    };
+
+    let auto = columns(MarkdownOptions::default());
+    assert_eq!(4, auto.len());
+    assert!(auto.iter().all(|c| *c == Sizing::Auto));
+
+    let fit = columns(MarkdownOptions {
+        tables: TableWidths::Fit,
+        ..Default::default()
+    });
+    assert_eq!(4, fit.len());
+    assert!(fit.iter().all(|c| matches!(c, Sizing::Fr(_))));
+    // the wordier column gets more of the page
+    assert!(matches!((fit[0], fit[1]), (Sizing::Fr(a), Sizing::Fr(b)) if b > a));
+}
+
+#[test]
+fn preamble_renders_separately() -> Result<()> {
+    let config = Config::from_str(EXAMPLE_ONE_CONTENT, Some("one.toml"))?;
+    let html = config.render_preamble_html().unwrap().unwrap();
+    assert!(html.contains("This packet includes problems"));
+    let world = TypstWrapperWorld::new("");
+    let content = config.render_preamble_content(&world).unwrap().unwrap();
+    assert!(content
+        .plain_text()
+        .contains("This packet includes problems"));
+
+    let mut config = config;
+    config.packet.preamble = None;
+    assert_eq!(None, config.render_preamble_html().unwrap());
+    assert!(config.render_preamble_content(&world).unwrap().is_none());
+    Ok(())
+}
+
+#[test]
+fn packet_renders_to_html() -> Result<()> {
+    let config = Config::from_str(EXAMPLE_ONE_CONTENT, Some("one.toml"))?;
+    let packet = config.render_html().unwrap();
+    assert!(!packet.html.is_empty());
+    assert!(!packet.css.is_empty());
+    for class in [
+        "packet",
+        "packet-title",
+        "problem",
+        "problem-title",
+        "test-case",
+    ] {
+        assert!(packet.html.contains(&format!("class=\"{}\"", class)));
+        assert!(packet.css.contains(&format!(".{} ", class)));
+    }
+    let title = &config.packet.problem(0).unwrap().title;
+    assert!(packet.html.contains(title.as_str()));
+    // the stylesheet is not inlined into the body
+    assert!(!packet.html.contains("<style"));
+    Ok(())
+}
+
+#[test]
+fn ragged_tables_are_padded() {
+    let world = TypstWrapperWorld::new("");
+    let table = "| a | b | c |\n|---|---|---|\n| 1 |\n| 1 | 2 | 3 | 4 |";
+    let content = render_markdown(table, &world).unwrap();
+    let table = content
+        .query_first(Selector::Elem(TableElem::elem(), None))
+        .unwrap();
+    let table = table.to_packed::<TableElem>().unwrap();
+    let rows = table
+        .children
+        .iter()
+        .filter(|c| matches!(c, TableChild::Item(_)))
+        .count();
+    assert_eq!(6, rows);
+    assert!(!content.plain_text().contains('4'));
+}
+
+#[test]
+fn memory_per_phase() {
+    let cases = [
+        (CommandConfig::Neither, None, None),
+        (CommandConfig::Both(256), Some(256), Some(256)),
+        (CommandConfig::Compile { compile: 512 }, Some(512), None),
+        (CommandConfig::Run { run: 128 }, None, Some(128)),
+        (
+            CommandConfig::Each {
+                compile: 512,
+                run: 128,
+            },
+            Some(512),
+            Some(128),
+        ),
+    ];
+    for (max_memory, compile, run) in cases {
+        let runner = TestRunner {
+            max_file_size: max_memory.clone(),
+            max_memory,
+            ..Default::default()
+        };
+        assert_eq!(compile, runner.memory_for(Phase::Compile));
+        assert_eq!(run, runner.memory_for(Phase::Run));
+        assert_eq!(compile, runner.file_size_for(Phase::Compile));
+        assert_eq!(run, runner.file_size_for(Phase::Run));
+    }
+}
+
+#[test]
+fn command_config_forms() {
+    let cases = [
+        ("", CommandConfig::Neither),
+        ("max_memory = {}", CommandConfig::Neither),
+        ("max_memory = 256", CommandConfig::Both(256)),
+        (
+            "max_memory = { compile = 512 }",
+            CommandConfig::Compile { compile: 512 },
+        ),
+        (
+            "max_memory = { run = 256 }",
+            CommandConfig::Run { run: 256 },
+        ),
+        (
+            "max_memory = { compile = 512, run = 256 }",
+            CommandConfig::Each {
+                compile: 512,
+                run: 256,
+            },
+        ),
+    ];
+
+    for (toml, expected) in cases {
+        let runner: TestRunner = toml_edit::de::from_str(toml).unwrap();
+        assert_eq!(expected, runner.max_memory, "parsing {:?}", toml);
+
+        let serialized = toml_edit::ser::to_string(&runner).unwrap();
+        let round_trip: TestRunner = toml_edit::de::from_str(&serialized).unwrap();
+        assert_eq!(runner, round_trip, "round tripping {:?}", toml);
+
+        let json = serde_json::to_string(&runner).unwrap();
+        assert_eq!(runner, serde_json::from_str::<TestRunner>(&json).unwrap());
+    }
+
+    assert!(toml_edit::de::from_str::<TestRunner>("max_memory = { compile = 1, rn = 2 }").is_err());
+    assert!(toml_edit::de::from_str::<TestRunner>("max_memory = \"256\"").is_err());
+}
+
+#[test]
+fn problem_tags() -> Result<()> {
+    let config = Config::from_str(
+        r#"
+[languages]
+python3 = "latest"
+
+[accounts]
+admins = []
+competitors = []
+
+[packet]
+title = "Tags"
+
+[[packet.problems]]
+title = "Knapsack"
+tags = ["dp", "math"]
+tests = []
+
+[[packet.problems]]
+title = "Shortest path"
+tags = ["graphs", "dp"]
+tests = []
+
+[[packet.problems]]
+title = "Untagged"
+tests = []
+"#,
+        Some("tags.toml"),
+    )?;
+
+    let world = TypstWrapperWorld::new("");
+    let value = config
+        .packet
+        .problem(0)
+        .unwrap()
+        .as_value(&world, HtmlMode::default().into())
+        .unwrap();
+    let typst::foundations::Value::Dict(dict) = value else {
+        panic!("problem should be a dictionary");
+    };
+    assert_eq!(
+        typst::foundations::Value::Array(
+            ["dp", "math"]
+                .into_iter()
+                .map(|t| typst::foundations::Value::Str(t.into()))
+                .collect()
+        ),
+        *dict.get("tags").unwrap()
+    );
+
+    assert_eq!(
+        BTreeSet::from(["dp".to_string(), "graphs".into(), "math".into()]),
+        config.packet.tags()
+    );
+    Ok(())
+}
+
+#[test]
+fn language_set_serialization_order() -> Result<()> {
+    let config = Config::from_str(
+        r#"
+[languages]
+rust = "latest"
+zig = { run = "./solution", source_file = "solution.zig" }
+java = "21"
+ada = { run = "./solution", source_file = "solution.adb" }
+python3 = "latest"
+javascript = "latest"
+
+[accounts]
+admins = []
+competitors = []
+
+[packet]
+title = "Ordering"
+problems = []
+"#,
+        Some("ordering.toml"),
+    )?;
+
+    let expected = ["ada", "java", "javascript", "python3", "rust", "zig"];
+    let json = serde_json::to_string(&*config.languages).unwrap();
+    let positions = expected
+        .iter()
+        .map(|name| json.find(&format!("\"{}\":", name)).unwrap())
+        .collect::<Vec<_>>();
+    assert!(positions.windows(2).all(|w| w[0] < w[1]), "{}", json);
+
+    let round_trip: LanguageSet = serde_json::from_str(&json).unwrap();
+    assert_eq!(*config.languages, round_trip);
+    assert_eq!(json, serde_json::to_string(&round_trip).unwrap());
+    Ok(())
+}
+
+#[test]
+fn duplicate_language_declarations() {
+    let parse = |languages: &str| {
+        let content = format!(
+            r#"
+[languages]
+{}
+
+[accounts]
+admins = []
+competitors = []
+
+[packet]
+title = "Duplicates"
+problems = []
+"#,
+            languages
+        );
+        Config::from_str(content, Some("duplicates.toml")).map_err(|e| format!("{:?}", e))
+    };
+
+    let err = parse(
+        r#"py = "latest"
+python3 = { run = "python3 solution.py", source_file = "solution.py" }"#,
+    )
+    .unwrap_err();
+    assert!(err.contains("Language 'python3' is declared more than once"));
+
+    let err = parse("js = \"latest\"\nnode = \"latest\"").unwrap_err();
+    assert!(err.contains("Language 'javascript' is declared more than once"));
+}
+
+#[test]
+fn misspelled_custom_language_field() {
+    let content = r#"
+[languages]
+ocaml = { runn = "./solution", source_file = "solution.ml" }
+
+[accounts]
+admins = []
+competitors = []
+
+[packet]
+title = "Typos"
+problems = []
+"#;
+    let err = Config::from_str(content, Some("typos.toml")).unwrap_err();
+    let err = format!("{:?}", err);
+    assert!(err.contains("unknown field `runn`"), "{}", err);
+    assert!(err.contains("`source_file`"), "{}", err);
+
+    let content = content
+        .replace("runn", "run")
+        .replace("source_file", "source");
+    let err = Config::from_str(content, Some("typos.toml")).unwrap_err();
+    assert!(format!("{:?}", err).contains("unknown field `source`"));
+}
+
+#[test]
+fn lints() -> Result<()> {
+    let config = Config::from_str(
+        r#"
+[languages]
+python3 = "latest"
+java = "21"
+
+[accounts]
+admins = []
+competitors = []
+
+[packet]
+title = "Lints"
+
+[[packet.problems]]
+title = "Hidden"
+languages = ["python3"]
+
+[[packet.problems.tests]]
+input = "a"
+output = "a"
+"#,
+        Some("lints.toml"),
+    )?;
+
+    let lints = config.lint();
+    assert_eq!(3, lints.len());
+    assert!(lints
+        .iter()
+        .any(|l| l.severity == validate::Severity::Warning
+            && l.message.contains("'Hidden' has no visible tests")));
+    assert!(lints
+        .iter()
+        .any(|l| l.message.contains("'java' is not allowed by any problem")));
+    assert!(!lints.iter().any(|l| l.message.contains("'python3'")));
+    Ok(())
+}
+
+#[test]
+fn language_restriction_lint() -> Result<()> {
+    let config = Config::from_str(
+        r#"
+[languages]
+python3 = "latest"
+c = { build = "gcc -o solution solution.c", run = "./solution", source_file = "solution.c" }
+
+[accounts]
+admins = []
+competitors = []
+
+[packet]
+title = "Systems"
+
+[[packet.problems]]
+title = "Pointers"
+languages = ["c"]
+
+[[packet.problems.tests]]
+input = "a"
+output = "a"
+visible = true
+
+[[packet.problems]]
+title = "Anything"
+
+[[packet.problems.tests]]
+input = "a"
+output = "a"
+visible = true
+"#,
+        Some("systems.toml"),
+    )?;
+
+    assert_eq!(
+        vec![validate::Lint {
+            severity: validate::Severity::Info,
+            message: "Problem 'Pointers' only allows c, so it cannot be solved in python3".into(),
+        }],
+        config.lint()
+    );
+    Ok(())
+}
+
+#[test]
+fn problem_languages_accept_aliases() -> Result<()> {
+    let config = Config::from_str(
+        r#"
+[languages]
+python3 = "latest"
+java = "21"
+rust = "latest"
+
+[accounts]
+admins = []
+competitors = []
+
+[packet]
+title = "Aliases"
+
+[[packet.problems]]
+title = "Snakes"
+languages = ["py", "Java"]
+
+[[packet.problems.tests]]
+input = "a"
+output = "a"
+visible = true
+"#,
+        Some("aliases.toml"),
+    )?;
+
+    assert_eq!(Ok(()), config.validate());
+    let names = config
+        .effective_languages(&config.packet.problems[0])
+        .into_iter()
+        .map(Language::raw_name)
+        .collect::<Vec<_>>();
+    assert_eq!(vec!["java", "python3"], names);
+
+    let lints = config.lint();
+    assert_eq!(2, lints.len(), "{:?}", lints);
+    assert!(lints[0].message.contains("cannot be solved in rust"));
+    assert!(lints[1]
+        .message
+        .contains("'rust' is not allowed by any problem"));
+    Ok(())
+}
+
+#[test]
+fn example_test_is_first_visible() {
+    let test = |input: &str, visible| packet::Test {
+        input: input.into(),
+        output: input.into(),
+        visible,
+        ..Default::default()
+    };
+    let problem = packet::Problem {
+        title: "Example".into(),
+        tests: vec![test("1", false), test("2", true), test("3", true)],
+        ..Default::default()
+    };
+    assert_eq!("2", problem.example_test().unwrap().input);
+    assert_eq!(
+        vec!["2", "3"],
+        problem
+            .visible_tests()
+            .map(|t| t.input.as_str())
+            .collect::<Vec<_>>()
+    );
+}
+
+#[test]
+fn search_problems() {
+    let problem = |title: &str, description: &str| {
+        RawOrImport::from(packet::Problem {
+            title: title.into(),
+            description: Some(MarkdownRenderable::from_raw(description).into()),
+            ..Default::default()
+        })
+    };
+    let packet = packet::Packet {
+        problems: vec![
+            problem("Guessing Game", "Use a *binary\nsearch* to find $n$."),
+            problem("Binary Trees", "Count the `leaves`."),
+            problem("Sorting", "Sort the list."),
+        ],
+        ..Default::default()
+    };
+    assert_eq!(
+        Some("Use a binary search to find n.".into()),
+        packet.problem(0).unwrap().description_plain()
+    );
+    let titles = |query| {
+        packet
+            .search(query)
+            .into_iter()
+            .map(|p| p.title.as_str())
+            .collect::<Vec<_>>()
+    };
+    assert_eq!(vec!["Guessing Game", "Binary Trees"], titles("BINARY"));
+    assert_eq!(vec!["Guessing Game"], titles("binary search"));
+    assert_eq!(vec!["Binary Trees"], titles("leaves"));
+    assert!(titles("graphs").is_empty());
+}
+
+#[test]
+fn problem_value_has_example() -> Result<()> {
+    let mut config = Config::from_str(EXAMPLE_ONE_CONTENT, Some("one.toml"))?;
+    let world = TypstWrapperWorld::new("");
+    let problem = &mut config.packet.problems[0];
+    problem.tests[0].output.push('\n');
+    let Value::Dict(dict) = problem.as_value(&world, Default::default()).unwrap() else {
+        panic!("problem should be a dictionary");
+    };
+    let Value::Dict(example) = dict.get("example").unwrap() else {
+        panic!("example should be a dictionary");
+    };
+    assert_eq!(Value::Str("hello".into()), *example.get("input").unwrap());
+    assert_eq!(Value::Str("olleh".into()), *example.get("output").unwrap());
+
+    for test in problem.tests.iter_mut() {
+        test.visible = false;
+    }
+    let Value::Dict(dict) = problem.as_value(&world, Default::default()).unwrap() else {
+        panic!("problem should be a dictionary");
+    };
+    assert!(dict.get("example").is_err());
+    Ok(())
+}
+
+#[test]
+fn account_order_does_not_change_hash() -> Result<()> {
+    let user = |name: &str| User {
+        name: name.into(),
+        password: "pw".into(),
+    };
+    let build = |competitors| {
+        Config::builder()
+            .accounts(Accounts {
+                admins: vec![user("Teacher")],
+                competitors,
+            })
+            .build()
+    };
+    let forwards = build(vec![user("Ann"), user("Bob")]);
+    let backwards = build(vec![user("Bob"), user("Ann")]);
+    assert_eq!(forwards.hash(), backwards.hash());
+    // the order in which they were given is kept
+    assert_eq!("Bob", backwards.accounts.competitors[0].name);
+
+    let parse = |first: &str, second: &str| {
+        let content = format!(
+            "[languages]\npython3 = \"latest\"\n\n[accounts]\nadmins = []\ncompetitors = [{}, {}]\n\n[packet]\ntitle = \"T\"\nproblems = []",
+            first, second
+        );
+        Config::from_str(content, Some("accounts.toml")).unwrap()
+    };
+    let ann = "{ name = \"Ann\", password = \"a\" }";
+    let bob = "{ name = \"Bob\", password = \"b\" }";
+    let (one, two) = (parse(ann, bob), parse(bob, ann));
+    assert_eq!(one.hash(), two.hash());
+    // the order in which they were written is kept
+    assert_eq!("Bob", two.accounts.competitors[0].name);
+    Ok(())
+}
+
+#[test]
+fn config_summary() -> Result<()> {
+    let config = Config::from_str(EXAMPLE_ONE_CONTENT, Some("one.toml"))?;
+    assert_eq!(
+        ConfigSummary {
+            title: "Example Packet".into(),
+            problem_count: 1,
+            test_count: 5,
+            visible_test_count: 2,
+            language_count: 3,
+            admin_count: 1,
+            competitor_count: 2,
+            port: 80,
+        },
+        config.summary()
+    );
+    Ok(())
+}
+
+#[test]
+fn test_counts() {
+    let test = |visible| packet::Test {
+        input: "1".into(),
+        output: "1".into(),
+        visible,
+        ..Default::default()
+    };
+    let problem = |title: &str, tests| {
+        RawOrImport::from(packet::Problem {
+            title: title.into(),
+            tests,
+            ..Default::default()
+        })
+    };
+    let packet = packet::Packet {
+        problems: vec![
+            problem("Mixed", vec![test(true), test(false), test(false)]),
+            problem("Visible", vec![test(true), test(true)]),
+        ],
+        ..Default::default()
+    };
+    assert_eq!(5, packet.test_count());
+    assert_eq!(3, packet.visible_test_count());
+
+    let mixed = packet.problem(0).unwrap();
+    assert_eq!(3, mixed.test_count());
+    assert_eq!(1, mixed.visible_test_count());
+    let visible = packet.problem(1).unwrap();
+    assert_eq!(2, visible.test_count());
+    assert_eq!(2, visible.visible_test_count());
+}
+
+#[test]
+fn all_hidden_tests_lint() {
+    let test = |visible| packet::Test {
+        input: "1".into(),
+        output: "1".into(),
+        visible,
+        ..Default::default()
+    };
+    let hidden = packet::Problem {
+        title: "Hidden".into(),
+        tests: vec![test(false), test(false), test(false)],
+        ..Default::default()
+    };
+    assert_eq!(
+        vec![validate::Lint {
+            severity: validate::Severity::Warning,
+            message:
+                "Problem 'Hidden' has no visible tests, so competitors will not see an example"
+                    .into(),
+        }],
+        hidden.lint()
+    );
+
+    let example = packet::Problem {
+        title: "Example".into(),
+        tests: vec![test(false), test(true)],
+        ..Default::default()
+    };
+    assert!(example.lint().is_empty());
+}
+
+#[test]
+fn test_output_matching() {
+    let test = packet::Test {
+        output: " hello world".into(),
+        ..Default::default()
+    };
+    let trimmed = TestRunner::default();
+    let exact = TestRunner {
+        comparison: ComparisonMode::Exact,
+        ..Default::default()
+    };
+    assert!(test.matches("hello world   ", &trimmed));
+    assert!(!test.matches("hello world   ", &exact));
+    assert!(test.matches(" hello world", &exact));
+    assert!(!test.matches("hello  world", &trimmed));
+}
+
+#[test]
+fn test_exit_code_and_stderr() {
+    let test: packet::Test = toml_edit::de::from_str(
+        r#"
+input = "-1"
+output = ""
+expected_exit_code = 2
+"#,
+    )
+    .unwrap();
+    let output = |stderr, exit_code| packet::ActualOutput {
+        stdout: "",
+        stderr,
+        exit_code,
+    };
+    let runner = TestRunner::default();
+    assert!(test.matches(output("anything", 2), &runner));
+    assert!(!test.matches(output("", 0), &runner));
+    // a plain string is a successful run
+    assert!(!test.matches("", &runner));
+
+    let test = packet::Test {
+        output: "0".into(),
+        expected_stderr: Some("warning: empty input\n".into()),
+        ..Default::default()
+    };
+    let output = |stderr| packet::ActualOutput {
+        stdout: "0",
+        stderr,
+        exit_code: 0,
+    };
+    let exact = TestRunner {
+        comparison: ComparisonMode::Exact,
+        ..Default::default()
+    };
+    assert!(test.matches(output("warning: empty input\r\n"), &exact));
+    assert!(!test.matches(output("error: empty input\n"), &runner));
+    assert!(!test.matches("0", &runner));
+
+    // stdout is still compared when stderr and the exit code match
+    assert!(!runner.output_matches(
+        &test,
+        packet::ActualOutput {
+            stdout: "1",
+            ..output("warning: empty input\n")
+        }
+    ));
+}
+
+#[test]
+fn test_output_line_endings() {
+    let test = packet::Test {
+        output: "a\r\nb".into(),
+        ..Default::default()
+    };
+    let exact = TestRunner {
+        comparison: ComparisonMode::Exact,
+        ..Default::default()
+    };
+    let exact_endings = TestRunner {
+        normalize_line_endings: false,
+        ..exact.clone()
+    };
+    assert!(test.matches("a\nb", &exact));
+    assert!(!test.matches("a\nb", &exact_endings));
+    assert!(test.matches("a\r\nb", &exact_endings));
+
+    let runner = TestRunner::default();
+    assert!(runner.normalize_line_endings);
+    assert!(test.matches("a\nb\n", &runner));
+    assert!(runner.output_matches(&test, "a\nb\n"));
+}
+
+#[test]
+fn test_output_case_insensitive() {
+    let test = packet::Test {
+        output: "YES\n".into(),
+        ..Default::default()
+    };
+    let mut runner = TestRunner::default();
+    assert!(!runner.case_insensitive);
+    assert!(!test.matches("yes", &runner));
+    assert!(!runner.output_matches(&test, "Yes"));
+    runner.case_insensitive = true;
+    assert!(test.matches("yes", &runner));
+    assert!(!test.matches("no", &runner));
+    assert!(runner.output_matches(&test, "Yes"));
+}
+
+#[test]
+fn test_output_comparison_modes() {
+    let test = packet::Test {
+        output: "1 2".into(),
+        ..Default::default()
+    };
+    let runner = |comparison| TestRunner {
+        comparison,
+        ..Default::default()
+    };
+    assert!(!test.matches("1  2\n", &runner(ComparisonMode::Exact)));
+    assert!(!test.matches("1  2\n", &runner(ComparisonMode::Trimmed)));
+    assert!(test.matches("1  2\n", &runner(ComparisonMode::Tokens)));
+    assert!(test.matches("1 2\n", &runner(ComparisonMode::Trimmed)));
+    assert!(!test.matches("12", &runner(ComparisonMode::Tokens)));
+}
+
+#[test]
+fn comparison_mode_from_trim_output() {
+    let runner = |toml: &str| toml_edit::de::from_str::<TestRunner>(toml).unwrap();
+    assert_eq!(ComparisonMode::Trimmed, runner("").comparison);
+    assert_eq!(
+        ComparisonMode::Trimmed,
+        runner("trim_output = true").comparison
+    );
+    assert_eq!(
+        ComparisonMode::Exact,
+        runner("trim_output = false").comparison
+    );
+    assert_eq!(
+        ComparisonMode::Tokens,
+        runner("comparison = \"tokens\"").comparison
+    );
+    assert!(toml_edit::de::from_str::<TestRunner>("comparison = \"fuzzy\"").is_err());
+
+    let serialized = toml_edit::ser::to_string(&runner("trim_output = false")).unwrap();
+    assert!(serialized.contains("comparison = \"exact\""));
+}
+
+#[test]
+fn test_io_from_files() {
+    let test: packet::Test = toml_edit::de::from_str(
+        r#"
+input_file = "./tests/data/hello.in"
+output_file = "./tests/data/hello.out"
+visible = true
+"#,
+    )
+    .unwrap();
+    assert_eq!("hello\n", test.input);
+    assert_eq!("olleh\n", test.output);
+    assert!(test.visible);
+}
+
+#[test]
+fn test_io_files_are_relative_to_the_config() -> Result<()> {
+    let dir = tempfile::tempdir().unwrap();
+    std::fs::write(dir.path().join("imported.in"), "imported\n").unwrap();
+    let imported = dir.path().join("imported.toml");
+    std::fs::write(
+        &imported,
+        r#"
+title = "Imported"
+tests = [{ input_file = "imported.in", output = "" }]
+"#,
+    )
+    .unwrap();
+
+    // the tests run in the crate's root, not in `tests/data`
+    let config = Config::from_str(
+        format!(
+            r#"
+[languages]
+python3 = "latest"
+
+[accounts]
+admins = []
+competitors = []
+
+[packet]
+title = "Files"
+
+[[packet.problems]]
+title = "Reverse"
+tests = [{{ input_file = "hello.in", output_file = "hello.out" }}]
+
+[[packet.problems]]
+import = "{}"
+"#,
+            imported.display().to_string().replace('\\', "/")
+        ),
+        Some("./tests/data/files.toml"),
+    )?;
+
+    let test = &config.packet.problems[0].tests[0];
+    assert_eq!("hello\n", test.input);
+    assert_eq!("olleh\n", test.output);
+    let data = std::path::Path::new("./tests/data");
+    assert_eq!(Some(data.join("hello.in")), test.input_file);
+    assert!(config.dependency_paths().contains(&data.join("hello.out")));
+
+    // imported problems are relative to the file they are imported from
+    assert_eq!("imported\n", config.packet.problems[1].tests[0].input);
+    Ok(())
+}
+
+#[test]
+fn test_io_inline() {
+    let test: packet::Test = toml_edit::de::from_str(
+        r#"
+input = "hello"
+output_file = "./tests/data/hello.out"
+"#,
+    )
+    .unwrap();
+    assert_eq!("hello", test.input);
+    assert_eq!("olleh\n", test.output);
+    assert!(!test.visible);
+}
+
+#[test]
+fn test_io_inline_and_file_conflict() {
+    let err = toml_edit::de::from_str::<packet::Test>(
+        r#"
+input = "hello"
+input_file = "./tests/data/hello.in"
+output = "olleh"
+"#,
+    )
+    .unwrap_err();
+    assert!(err
+        .message()
+        .contains("only one of `input` and `input_file` may be specified"));
+}
+
+#[test]
+fn iterate_problems() -> Result<()> {
+    let config = Config::from_str(
+        r#"
+[languages]
+python3 = "latest"
+
+[accounts]
+admins = []
+competitors = []
+
+[packet]
+title = "Iteration"
+
+[[packet.problems]]
+title = "First"
+tests = []
+
+[[packet.problems]]
+import = "./tests/data/problem.toml"
+
+[[packet.problems]]
+title = "Third"
+tests = []
+"#,
+        Some("iterate.toml"),
+    )?;
+
+    assert_eq!(3, config.packet.problem_count());
+    assert_eq!(
+        vec!["First", "Imported problem", "Third"],
+        config
+            .packet
+            .iter_problems()
+            .map(|p| p.title.as_str())
+            .collect::<Vec<_>>()
+    );
+    assert_eq!(
+        Some("Imported problem"),
+        config.packet.problem(1).map(|p| p.title.as_str())
+    );
+    assert_eq!(None, config.packet.problem(3));
+    Ok(())
+}
+
+#[test]
+fn extra_font_dirs() {
+    let world = TypstWrapperWorld::new("");
+    assert!(!typst::World::book(&world).contains_family("tuffy"));
+
+    let world = TypstWrapperWorld::with_fonts(
+        "",
+        &FontConfig {
+            include_system: false,
+            extra_dirs: vec!["./tests/data/fonts".into()],
+        },
+    );
+    assert!(typst::World::book(&world).contains_family("tuffy"));
+}
+
+const FONT_CONFIG: &str = r#"
+[languages]
+python3 = "latest"
+
+[accounts]
+admins = []
+competitors = []
+
+[packet]
+title = "Fonts"
+problems = []
+
+[render]
+fonts = ["./tests/data/fonts/Tuffy.ttf"]
+"#;
+
+#[test]
+fn render_with_bundled_font() -> Result<()> {
+    let config = Config::from_str(FONT_CONFIG, Some("fonts.toml"))?;
+    let world = config
+        .typst_world(
+            Some("#set text(font: \"Tuffy\")\n#title".into()),
+            &Default::default(),
+        )
+        .unwrap();
+    let compiled = typst::compile(&world);
+    assert!(compiled.output.is_ok());
+    assert!(!compiled
+        .warnings
+        .iter()
+        .any(|w| w.message.contains("unknown font family")));
+    Ok(())
+}
+
+#[test]
+fn fonts_are_relative_to_the_config() -> Result<()> {
+    let config = Config::from_str(
+        FONT_CONFIG.replace("./tests/data/fonts/Tuffy.ttf", "fonts/Tuffy.ttf"),
+        Some("./tests/data/fonts.toml"),
+    )?;
+    assert_eq!(
+        std::path::Path::new("./tests/data/fonts/Tuffy.ttf"),
+        config.resolve_path(&config.render.fonts[0])
+    );
+    assert!(config
+        .dependency_paths()
+        .contains(&std::path::Path::new("./tests/data").join("fonts/Tuffy.ttf")));
+    let world = config
+        .typst_world(
+            Some("#set text(font: \"Tuffy\")\n#title".into()),
+            &Default::default(),
+        )
+        .unwrap();
+    let compiled = typst::compile(&world);
+    assert!(compiled.output.is_ok());
+    assert!(!compiled
+        .warnings
+        .iter()
+        .any(|w| w.message.contains("unknown font family")));
+    Ok(())
+}
+
+#[test]
+fn render_with_missing_font() -> Result<()> {
+    let config = Config::from_str(
+        FONT_CONFIG.replace("Tuffy.ttf", "Missing.ttf"),
+        Some("fonts.toml"),
+    )?;
+    let err = config.render_pdf(None).unwrap_err();
+    assert!(err.to_string().contains("Missing.ttf"));
+    Ok(())
+}
+
+#[test]
+fn broken_problem_fails_render() -> Result<()> {
+    let config = Config::from_str(
+        r#"
+[languages]
+python3 = "latest"
+
+[accounts]
+admins = []
+competitors = []
+
+[packet]
+title = "Broken"
+
+[[packet.problems]]
+title = "Good"
+description = "Reverse the input"
+tests = []
+
+[[packet.problems]]
+title = "Bad"
+description = "Some <b>HTML</b>"
+tests = []
+"#,
+        Some("broken.toml"),
+    )?;
+    let err = config.render_pdf(None).unwrap_err();
+    let render::RenderPdfError::Problems(errs) = &err else {
+        panic!("expected problem errors, got {:?}", err);
+    };
+    assert_eq!(1, errs.len());
+    assert_eq!("Bad", errs[0].0);
+    assert!(matches!(
+        errs[0].1,
+        render::markdown::RenderError::UnsupportedHtml { .. }
+    ));
+    assert!(err.to_string().contains("problem 'Bad'"));
+    Ok(())
+}
+
+fn frame_text(frame: &typst::layout::Frame, out: &mut String) {
+    for (_, item) in frame.items() {
+        match item {
+            typst::layout::FrameItem::Text(text) => out.push_str(&text.text),
+            typst::layout::FrameItem::Group(group) => frame_text(&group.frame, out),
+            _ => {}
+        }
+        out.push(' ');
+    }
+}
+
+#[test]
+fn per_problem_templates() -> Result<()> {
+    let config = Config::from_str(
+        r#"
+[languages]
+python3 = "latest"
+
+[accounts]
+admins = []
+competitors = []
+
+[packet]
+title = "Templates"
+
+[[packet.problems]]
+title = "Interactive"
+template = "./tests/data/interactive.typ"
+tests = []
+
+[[packet.problems]]
+title = "Standard"
+tests = []
+"#,
+        Some("templates.toml"),
+    )?;
+    let world = config.typst_world(None, &Default::default()).unwrap();
+    let document = typst::compile(&world).output.unwrap();
+    let mut text = String::new();
+    for page in &document.pages {
+        frame_text(&page.frame, &mut text);
+    }
+    assert_eq!(1, text.matches("CUSTOM-TEMPLATE-MARKER").count());
+    assert!(text.contains("Interactive (interactive)"));
+    assert!(text.contains("Standard"));
+    assert!(!text.contains("Standard (interactive)"));
+    Ok(())
+}
+
+#[test]
+fn problem_templates_are_relative_to_the_config() -> Result<()> {
+    let config = Config::from_str(
+        r#"
+[languages]
+python3 = "latest"
+
+[accounts]
+admins = []
+competitors = []
+
+[packet]
+title = "Templates"
+
+[[packet.problems]]
+title = "Interactive"
+template = "interactive.typ"
+tests = []
+"#,
+        Some("./tests/data/templates.toml"),
+    )?;
+    let world = config.typst_world(None, &Default::default()).unwrap();
+    let document = typst::compile(&world).output.unwrap();
+    let mut text = String::new();
+    for page in &document.pages {
+        frame_text(&page.frame, &mut text);
+    }
+    assert_eq!(1, text.matches("CUSTOM-TEMPLATE-MARKER").count());
+    assert!(config
+        .dependency_paths()
+        .contains(&std::path::Path::new("./tests/data").join("interactive.typ")));
+    Ok(())
+}
+
+#[test]
+fn svg_pages() -> Result<()> {
+    let config = Config::from_str(EXAMPLE_ONE_CONTENT, Some("one.toml"))?;
+    let pages = config.render_svg_pages(None).unwrap();
+    assert!(!pages.is_empty());
+    for page in &pages {
+        assert!(page.starts_with("<svg"));
+    }
+    Ok(())
+}
+
+#[test]
+fn table_of_contents() -> Result<()> {
+    let source = |render: &str| {
+        format!(
+            r#"
+[languages]
+python3 = "latest"
+
+[accounts]
+admins = []
+competitors = []
+
+{render}
+
+[packet]
+title = "Outlined Packet"
+
+[[packet.problems]]
+title = "Alpha Problem"
+tests = []
+
+[[packet.problems]]
+title = "Beta Problem"
+tests = []
+"#
+        )
+    };
+    let pages = |config: &Config| {
+        let world = config.typst_world(None, &Default::default()).unwrap();
+        let document = typst::compile(&world).output.unwrap();
+        document
+            .pages
+            .iter()
+            .map(|page| {
+                let mut text = String::new();
+                frame_text(&page.frame, &mut text);
+                text
+            })
+            .collect::<Vec<_>>()
+    };
+
+    let plain = pages(&Config::from_str(source(""), Some("toc.toml"))?);
+    let with_toc = pages(&Config::from_str(
+        source("[render]\ntoc = true"),
+        Some("toc.toml"),
+    )?);
+    assert_eq!(plain.len() + 1, with_toc.len());
+
+    let toc = with_toc
+        .iter()
+        .find(|page| page.contains("Contents"))
+        .expect("a page with the table of contents");
+    for title in ["Alpha Problem", "Beta Problem"] {
+        assert!(toc.contains(title));
+        assert_eq!(2, with_toc.iter().filter(|p| p.contains(title)).count());
+    }
+    Ok(())
+}
+
+#[test]
+fn page_setup() -> Result<()> {
+    let page_size = |render: &str| {
+        let config = Config::from_str(
+            format!(
+                r#"
+[languages]
+python3 = "latest"
+
+[accounts]
+admins = []
+competitors = []
+
+[packet]
+title = "Page Setup"
+problems = []
+
+[render]
+{}
+"#,
+                render
+            ),
+            Some("page-setup.toml"),
+        )
+        .unwrap();
+        let world = config.typst_world(None, &Default::default())?;
+        let document = typst::compile(&world).output.unwrap();
+        Ok::<_, std::io::Error>(document.pages[0].frame.size())
+    };
+
+    let a4 = page_size(r#"page_size = "a4""#).unwrap();
+    let letter = page_size(r#"page_size = "letter""#).unwrap();
+    assert_ne!(a4, letter);
+    assert!((a4.x.to_mm() - 210.0).abs() < 0.01);
+    assert!((letter.x.to_inches() - 8.5).abs() < 0.01);
+    assert_eq!(
+        a4,
+        page_size("page_size = \"a4\"\nmargin = \"1cm\"\ncolumns = 2").unwrap()
+    );
+
+    let err = page_size(r#"page_size = "napkin""#).unwrap_err();
+    assert_eq!("Unknown page size: 'napkin'", err.to_string());
+    let err = page_size(r#"margin = "wide""#).unwrap_err();
+    assert_eq!("Invalid margin: 'wide'", err.to_string());
+    Ok(())
+}
+
+#[test]
+fn pdf_metadata() -> Result<()> {
+    let config = Config::from_str(
+        r#"
+[languages]
+python3 = "latest"
+
+[accounts]
+admins = []
+competitors = []
+
+[packet]
+title = "Metadata Packet"
+author = "Basalt"
+keywords = ["competition"]
+problems = []
+"#,
+        Some("metadata.toml"),
+    )?;
+    let pdf = config.render_pdf(None).unwrap();
+    let contains = |needle: &[u8]| pdf.windows(needle.len()).any(|w| w == needle);
+    assert!(contains(b"/Title (Metadata Packet)"));
+    assert!(contains(b"/Author (Basalt)"));
+    assert!(contains(b"/Keywords (competition)"));
+    Ok(())
+}
+
+#[test]
+fn render_pdf_into_reuses_buffer() -> Result<()> {
+    let config = Config::from_str(EXAMPLE_ONE_CONTENT, Some("one.toml"))?;
+    let mut buf = b"stale".to_vec();
+    config.render_pdf_into(&mut buf, None).unwrap();
+    assert!(buf.starts_with(b"%PDF"));
+    let len = buf.len();
+
+    config.render_pdf_into(&mut buf, None).unwrap();
+    assert!(buf.starts_with(b"%PDF"));
+    assert_eq!(len, buf.len());
+    Ok(())
+}
+
+#[test]
+fn fixed_time_is_reproducible() -> Result<()> {
+    let config = Config::from_str(EXAMPLE_ONE_CONTENT, Some("one.toml"))?;
+    let options = RenderOptions {
+        time: Some(time::OffsetDateTime::from_unix_timestamp(1_740_821_400).unwrap()),
+        ..Default::default()
+    };
+    let template = Some(String::from("#datetime.today().display()"));
+    let first = config.render_pdf_with(template.clone(), &options).unwrap();
+    let second = config.render_pdf_with(template, &options).unwrap();
+    assert_eq!(first, second);
+    Ok(())
+}
+
+#[cfg(feature = "json")]
+#[test]
+fn json_matches_toml() -> Result<()> {
+    let mut toml = Config::from_str(EXAMPLE_ONE_CONTENT, Some("one.toml"))?;
+    let mut json = Config::from_json_str(include_str!("../tests/data/one.json"), Some("one.json"))?;
+    // the hash is of the raw file, so it will always differ
+    toml.hash = 0;
+    json.hash = 0;
+    assert_eq!(toml, json);
+    Ok(())
+}
+
+#[cfg(feature = "yaml")]
+#[test]
+fn yaml_matches_toml() -> Result<()> {
+    let mut toml = Config::from_str(EXAMPLE_ONE_CONTENT, Some("one.toml"))?;
+    let mut yaml = Config::from_yaml_str(include_str!("../tests/data/one.yaml"), Some("one.yaml"))?;
+    // the hash is of the raw file, so it will always differ
+    toml.hash = 0;
+    yaml.hash = 0;
+    assert_eq!(toml, yaml);
+
+    // versions may be written as numbers
+    assert_eq!(
+        Version::Specific("21".into()),
+        serde_yaml::from_str::<Version>("21").unwrap()
+    );
+    assert_eq!(
+        Version::Latest,
+        serde_yaml::from_str::<Version>("'*'").unwrap()
+    );
+    assert!(serde_yaml::from_str::<Version>("3.12").is_err());
+    Ok(())
+}
+
+#[cfg(feature = "json")]
+#[test]
+fn malformed_json() {
+    let err = Config::from_json_str("{\n  \"port\": \"eighty\"\n}", Some("bad.json")).unwrap_err();
+    assert!(matches!(err, ConfigReadError::MalformedData(_)));
+}
+
+#[test]
+fn missing_sections() {
+    let missing = |content: &str| match Config::from_str(content, Some("missing.toml")) {
+        Err(ConfigReadError::MissingSection(err)) => err,
+        other => panic!("expected a missing section, got {:?}", other),
+    };
+
+    let err =
+        missing("[languages]\npython3 = \"latest\"\n\n[accounts]\nadmins = []\ncompetitors = []");
+    assert_eq!("packet", err.section);
+    assert_eq!(
+        "The config is missing the required `packet` section",
+        err.to_string()
+    );
+    let help = miette::Diagnostic::help(&err).unwrap().to_string();
+    assert!(help.contains("[packet]\ntitle = "), "{}", help);
+    let labels = miette::Diagnostic::labels(&err)
+        .unwrap()
+        .collect::<Vec<_>>();
+    assert_eq!(0, labels[0].offset());
+
+    let err = missing(
+        "[accounts]\nadmins = []\ncompetitors = []\n\n[packet]\ntitle = \"T\"\nproblems = []",
+    );
+    assert_eq!("languages", err.section);
+    assert!(err.to_string().contains("`languages`"));
+}
+
+fn config_with_times(start: &str, end: &str) -> Config {
+    Config::from_str(
+        format!(
+            r#"
+start_time = "{}"
+end_time = "{}"
+
+[languages]
+python3 = "latest"
+
+[accounts]
+admins = []
+competitors = []
+
+[packet]
+title = "Timed"
+problems = []
+"#,
+            start, end
+        ),
+        Some("timed.toml"),
+    )
+    .unwrap()
+}
+
+#[test]
+fn competition_window() {
+    let at = |s: &str| {
+        time::OffsetDateTime::parse(s, &time::format_description::well_known::Rfc3339).unwrap()
+    };
+    let config = config_with_times("2025-03-01T09:00:00-05:00", "2025-03-01T12:00:00-05:00");
+    assert_eq!(Ok(()), config.validate());
+    assert_eq!(Some(Duration::from_secs(3 * 60 * 60)), config.duration());
+
+    assert!(config.is_active(at("2025-03-01T09:00:00-05:00")));
+    assert!(config.is_active(at("2025-03-01T16:30:00Z")));
+    assert!(!config.is_active(at("2025-03-01T08:59:59-05:00")));
+    assert!(!config.is_active(at("2025-03-01T12:00:00-05:00")));
+
+    let always = Config::from_str(EXAMPLE_ONE_CONTENT, Some("one.toml")).unwrap();
+    assert!(always.is_active(at("2025-03-01T09:00:00Z")));
+    assert_eq!(None, always.duration());
+}
+
+#[test]
+fn competition_ends_before_it_starts() {
+    let config = config_with_times("2025-03-01T12:00:00Z", "2025-03-01T09:00:00Z");
+    assert_eq!(None, config.duration());
+    assert!(matches!(
+        config.validate().unwrap_err()[..],
+        [validate::ValidationError::EndBeforeStart { .. }]
+    ));
+}
+
+#[test]
+fn no_languages() {
+    let empty = Config::from_str(
+        "[languages]\n\n[accounts]\nadmins = []\ncompetitors = []\n\n[packet]\ntitle = \"T\"\nproblems = []",
+        Some("empty.toml"),
+    )
+    .unwrap();
+    assert_eq!(
+        Err(vec![validate::ValidationError::NoLanguages]),
+        empty.validate()
+    );
+
+    let missing = Config::from_str(
+        "[accounts]\nadmins = []\ncompetitors = []\n\n[packet]\ntitle = \"T\"\nproblems = []",
+        Some("missing.toml"),
+    );
+    assert!(matches!(
+        missing,
+        Err(ConfigReadError::MissingSection(MissingSectionError {
+            section: "languages",
+            ..
+        }))
+    ));
+}
+
+#[test]
+fn empty_custom_language_commands() -> Result<()> {
+    let config = Config::from_str(
+        r#"
+[languages]
+python3 = "latest"
+ocaml = { run = "", source_file = "solution.ml" }
+
+[accounts]
+admins = []
+competitors = []
+
+[packet]
+title = "T"
+problems = []
+"#,
+        Some("empty-run.toml"),
+    )?;
+    assert_eq!(
+        Err(vec![validate::ValidationError::EmptyLanguageField {
+            language: "ocaml".into(),
+            field: "run",
+        }]),
+        config.validate()
+    );
+    Ok(())
+}
+
+#[cfg(feature = "json")]
+#[test]
+fn missing_sections_json() {
+    let err = Config::from_json_str(
+        r#"{ "languages": {}, "accounts": {}, "port": 80 }"#,
+        None::<&str>,
+    )
+    .unwrap_err();
+    // `accounts` is there but incomplete, which is not a missing section
+    assert!(matches!(err, ConfigReadError::MalformedData(_)));
+
+    let err = Config::from_json_str(r#"{ "languages": {} }"#, None::<&str>).unwrap_err();
+    assert!(matches!(
+        err,
+        ConfigReadError::MissingSection(MissingSectionError {
+            section: "accounts",
+            ..
+        })
+    ));
+}
+
+#[test]
+fn diff_configs() -> Result<()> {
+    let mut config = Config::from_str(EXAMPLE_ONE_CONTENT, Some("one.toml"))?;
+    config.packet.problems.push(
+        packet::Problem {
+            title: "Second".into(),
+            tests: vec![packet::Test {
+                input: "1".into(),
+                output: "2".into(),
+                ..Default::default()
+            }],
+            ..Default::default()
+        }
+        .into(),
+    );
+    assert!(config.diff(&config).is_empty());
+
+    let mut modified = config.clone();
+    modified.packet.problems[1].tests[0].output.push('!');
+    assert_eq!(
+        ConfigDiff {
+            modified_problems: vec!["Second".into()],
+            ..Default::default()
+        },
+        config.diff(&modified)
+    );
+
+    modified.packet.problems.remove(0);
+    modified.port += 1;
+    let first = config.packet.problems[0].title.clone();
+    let diff = config.diff(&modified);
+    assert_eq!(vec![first.clone()], diff.removed_problems);
+    assert_eq!(vec!["Second".to_string()], diff.modified_problems);
+    assert!(diff.added_problems.is_empty());
+    assert!(diff.port_changed);
+    assert!(!diff.languages_changed);
+    assert!(!diff.accounts_changed);
+
+    assert_eq!(vec![first], modified.diff(&config).added_problems);
+    Ok(())
+}
+
+#[test]
+fn problem_fingerprints() -> Result<()> {
+    let mut config = Config::from_str(EXAMPLE_ONE_CONTENT, Some("one.toml"))?;
+    config.packet.problems.push(
+        packet::Problem {
+            title: "Second".into(),
+            description: Some(MarkdownRenderable::from("Add one").into()),
+            ..Default::default()
+        }
+        .into(),
+    );
+    let before = config.problem_fingerprints();
+    assert_eq!(2, before.len());
+    assert_eq!(before, config.clone().problem_fingerprints());
+
+    config.packet.problems[1].description = Some(MarkdownRenderable::from("Add two").into());
+    let after = config.problem_fingerprints();
+    assert_eq!(before[0], after[0]);
+    assert_eq!(before[1].0, after[1].0);
+    assert_ne!(before[1].1, after[1].1);
+    Ok(())
+}
+
+#[test]
+fn packet_hash_ignores_accounts() -> Result<()> {
+    let config = Config::from_str(EXAMPLE_ONE_CONTENT, Some("one.toml"))?;
+
+    let mut accounts_changed = config.clone();
+    accounts_changed.accounts.competitors.push(User {
+        name: "StudentThree".into(),
+        password: "hunter2".into(),
+    });
+    accounts_changed.port = 8080;
+    assert_eq!(config.packet_hash(), accounts_changed.packet_hash());
+
+    let mut problem_changed = config.clone();
+    problem_changed.packet.problems[0].title = "Reversing a list".into();
+    assert_ne!(config.packet_hash(), problem_changed.packet_hash());
+    Ok(())
+}
+
+#[test]
+fn builtin_language_enumeration() {
+    assert!(BuiltInLanguage::all().contains(&BuiltInLanguage::Java));
+    assert_eq!(
+        BuiltInLanguage::VARIANTS.len(),
+        BuiltInLanguage::all().len()
+    );
+    assert_eq!(
+        vec!["8", "11", "21"],
+        BuiltInLanguage::Java.versions().collect::<Vec<_>>()
+    );
+    assert_eq!(
+        vec!["latest"],
+        BuiltInLanguage::Python3.versions().collect::<Vec<_>>()
+    );
+}
+
+#[test]
+fn config_builder() {
+    let mut languages = LanguageSet::new();
+    languages.insert(Language::BuiltIn {
+        language: BuiltInLanguage::Rust,
+        version: Version::Latest,
+    });
+    let build = || {
+        Config::builder()
+            .languages(languages.clone())
+            .accounts(Accounts {
+                admins: vec![User {
+                    name: "Teacher".into(),
+                    password: "abc123".into(),
+                }],
+                competitors: vec![],
+            })
+            .packet(Packet {
+                title: "Built".into(),
+                ..Default::default()
+            })
+    };
+
+    let config = build().build();
+    assert_eq!(default_port(), config.port);
+    assert_eq!("Built", config.packet.title);
+    let hash = config.hash();
+    dbg!(&hash);
+    assert_eq!(hash, build().build().hash());
+    assert_ne!(hash, build().port(80).build().hash());
+
+    let at = |s: &str| {
+        time::OffsetDateTime::parse(s, &time::format_description::well_known::Rfc3339).unwrap()
+    };
+    let timed = build()
+        .start_time(at("2025-03-01T09:00:00Z"))
+        .end_time(at("2025-03-01T12:00:00Z"))
+        .build();
+    assert_eq!(Some(Duration::from_secs(3 * 60 * 60)), timed.duration());
+    assert!(timed.is_active(at("2025-03-01T10:00:00Z")));
+    assert!(!timed.is_active(at("2025-03-01T12:00:00Z")));
+    assert_eq!(None, config.duration());
+}
+
+#[test]
+fn copy_single_file() {
+    let dir = tempfile::tempdir().unwrap();
+    let copy = FileCopy {
+        from: "./tests/data/hello.in".into(),
+        to: "data/input.txt".into(),
+        ..Default::default()
+    };
+    assert!(!copy.is_glob());
+    copy.copy_into(&Config::default(), dir.path()).unwrap();
+    assert_eq!(
+        "hello\n",
+        std::fs::read_to_string(dir.path().join("data/input.txt")).unwrap()
+    );
+}
+
+#[test]
+fn copy_glob_into_directory() {
+    let dir = tempfile::tempdir().unwrap();
+    let copy = FileCopy {
+        from: "./tests/data/fixtures/*.txt".into(),
+        to: "fixtures".into(),
+        ..Default::default()
+    };
+    assert!(copy.is_glob());
+    assert_eq!(2, copy.sources(&Config::default()).unwrap().len());
+    copy.copy_into(&Config::default(), dir.path()).unwrap();
+
+    let fixtures = dir.path().join("fixtures");
+    assert_eq!(
+        "alpha\n",
+        std::fs::read_to_string(fixtures.join("a.txt")).unwrap()
+    );
+    assert_eq!(
+        "beta\n",
+        std::fs::read_to_string(fixtures.join("b.txt")).unwrap()
+    );
+    assert!(!fixtures.join("c.dat").exists());
+}
+
+#[test]
+fn copy_directory_tree() {
+    let dir = tempfile::tempdir().unwrap();
+    let copy = FileCopy {
+        from: "./tests/data/tree".into(),
+        to: "data".into(),
+        ..Default::default()
+    };
+    assert!(copy.is_dir(&Config::default()));
+    copy.copy_into(&Config::default(), dir.path()).unwrap();
+
+    let data = dir.path().join("data");
+    let read = |path: &str| std::fs::read_to_string(data.join(path)).unwrap();
+    assert_eq!("root\n", read("root.txt"));
+    assert_eq!("nested\n", read("nested/inner.txt"));
+    assert_eq!("deep\n", read("nested/deeper/deep.txt"));
+}
+
+#[test]
+fn copy_directory_with_exclusions() {
+    let source = tempfile::tempdir().unwrap();
+    let write = |path: &str| {
+        let path = source.path().join(path);
+        std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+        std::fs::write(path, "contents").unwrap();
+    };
+    write("main.py");
+    write("scratch.tmp");
+    write("lib/helper.py");
+    write("lib/cache.tmp");
+    write(".git/HEAD");
+    write(".git/objects/ab");
+
+    let dir = tempfile::tempdir().unwrap();
+    let copy = FileCopy {
+        from: source.path().into(),
+        to: "data".into(),
+        exclude: vec![".git".into(), "*.tmp".into()],
+        ..Default::default()
+    };
+    copy.copy_into(&Config::default(), dir.path()).unwrap();
+
+    let data = dir.path().join("data");
+    assert!(data.join("main.py").exists());
+    assert!(data.join("lib/helper.py").exists());
+    assert!(!data.join("scratch.tmp").exists());
+    assert!(!data.join("lib/cache.tmp").exists());
+    assert!(!data.join(".git").exists());
+
+    let copy = FileCopy {
+        exclude: vec!["[".into()],
+        ..copy
+    };
+    assert!(matches!(
+        copy.copy_into(&Config::default(), dir.path()),
+        Err(FileCopyError::Pattern { .. })
+    ));
+}
+
+#[cfg(unix)]
+#[test]
+fn copy_directory_skips_symlinks() {
+    let source = tempfile::tempdir().unwrap();
+    std::fs::write(source.path().join("real.txt"), "real").unwrap();
+    std::os::unix::fs::symlink(
+        source.path().join("real.txt"),
+        source.path().join("link.txt"),
+    )
+    .unwrap();
+
+    let dir = tempfile::tempdir().unwrap();
+    let mut copy = FileCopy {
+        from: source.path().into(),
+        to: "data".into(),
+        ..Default::default()
+    };
+    copy.copy_into(&Config::default(), dir.path()).unwrap();
+    assert!(dir.path().join("data/real.txt").exists());
+    assert!(!dir.path().join("data/link.txt").exists());
+
+    copy.follow_symlinks = true;
+    copy.copy_into(&Config::default(), dir.path()).unwrap();
+    assert_eq!(
+        "real",
+        std::fs::read_to_string(dir.path().join("data/link.txt")).unwrap()
+    );
+}
+
+#[test]
+fn copy_directory_into_file() {
+    let dir = tempfile::tempdir().unwrap();
+    std::fs::write(dir.path().join("data.txt"), "file").unwrap();
+    let copy = FileCopy {
+        from: "./tests/data/tree".into(),
+        to: "data.txt".into(),
+        ..Default::default()
+    };
+    assert!(matches!(
+        copy.copy_into(&Config::default(), dir.path()),
+        Err(FileCopyError::DirectoryIntoFile { .. })
+    ));
+    assert!(dir.path().join("data.txt").is_file());
+
+    // a directory can have an extension
+    let copy = FileCopy {
+        to: "v1.2".into(),
+        ..copy
+    };
+    copy.copy_into(&Config::default(), dir.path()).unwrap();
+    assert!(dir.path().join("v1.2/root.txt").is_file());
+}
+
+#[test]
+fn copy_glob_with_duplicate_names() {
+    let source = tempfile::tempdir().unwrap();
+    for (dir, contents) in [("a", "first"), ("b", "second")] {
+        std::fs::create_dir(source.path().join(dir)).unwrap();
+        std::fs::write(source.path().join(dir).join("x.txt"), contents).unwrap();
+    }
+
+    let dir = tempfile::tempdir().unwrap();
+    let copy = FileCopy {
+        from: source.path().join("*/x.txt"),
+        to: "data".into(),
+        ..Default::default()
+    };
+    assert!(matches!(
+        copy.copy_into(&Config::default(), dir.path()),
+        Err(FileCopyError::DuplicateDestination { .. })
+    ));
+    assert!(!dir.path().join("data/x.txt").exists());
+}
+
+#[test]
+fn copy_refuses_to_leave_test_dir() {
+    let dir = tempfile::tempdir().unwrap();
+    let test_dir = dir.path().join("test");
+    for to in ["../escaped.txt", "data/../../escaped.txt"] {
+        let copy = FileCopy {
+            from: "./tests/data/hello.in".into(),
+            to: to.into(),
+            ..Default::default()
+        };
+        assert!(matches!(
+            copy.copy_into(&Config::default(), &test_dir),
+            Err(FileCopyError::UnsafeDestination(_))
+        ));
+    }
+    let absolute = FileCopy {
+        from: "./tests/data/hello.in".into(),
+        to: dir.path().join("escaped.txt"),
+        ..Default::default()
+    };
+    assert!(matches!(
+        absolute.copy_into(&Config::default(), &test_dir),
+        Err(FileCopyError::UnsafeDestination(_))
+    ));
+    assert!(!dir.path().join("escaped.txt").exists());
+}
+
+/// A config with only python, and the files to copy for each test
+fn config_with_copies(copy_files: Vec<FileCopy>) -> Config {
+    let mut languages = LanguageSet::new();
+    languages.insert(Language::BuiltIn {
+        language: BuiltInLanguage::Python3,
+        version: Version::Latest,
+    });
+    Config::builder()
+        .languages(languages)
+        .test_runner(TestRunner {
+            copy_files,
+            ..Default::default()
+        })
+        .build()
+}
+
+#[test]
+fn copy_glob_without_matches() {
+    let copy = FileCopy {
+        from: "./tests/data/fixtures/*.nothing".into(),
+        to: "fixtures".into(),
+        ..Default::default()
+    };
+    assert!(matches!(
+        copy.sources(&Config::default()),
+        Err(FileCopyError::NoMatches(_))
+    ));
+
+    let config = config_with_copies(vec![copy]);
+    assert_eq!(
+        Err(vec![validate::ValidationError::NoCopyMatches(
+            "./tests/data/fixtures/*.nothing".into()
+        )]),
+        config.validate()
+    );
+}
+
+#[test]
+fn copy_missing_source() {
+    let config = config_with_copies(vec![
+        FileCopy {
+            from: "./tests/data/hello.in".into(),
+            to: "./data/../input.txt".into(),
+            ..Default::default()
+        },
+        FileCopy {
+            from: "./tests/data/helo.in".into(),
+            to: "input.txt".into(),
+            ..Default::default()
+        },
+    ]);
+    assert_eq!(
+        Err(vec![validate::ValidationError::MissingCopySource(
+            "./tests/data/helo.in".into()
+        )]),
+        config.validate()
+    );
+}
+
+#[test]
+fn copy_files_are_relative_to_the_config() {
+    let config = |copy_files: &str| {
+        Config::from_str(
+            format!(
+                r#"
+[languages]
+python3 = "latest"
+
+[accounts]
+admins = []
+competitors = []
+
+[test_runner]
+copy_files = [{}]
+
+[packet]
+title = "Copies"
+problems = []
+"#,
+                copy_files
+            ),
+            Some("./tests/data/copies.toml"),
+        )
+        .unwrap()
+    };
+
+    // the tests run in the crate's root, not in `tests/data`
+    let relative = config(
+        r#"{ from = "hello.in", to = "input.txt" }, { from = "fixtures/*.txt", to = "fixtures" }, { from = "tree", to = "tree" }"#,
+    );
+    assert_eq!(Ok(()), relative.validate());
+    assert!(relative
+        .dependency_paths()
+        .contains(&std::path::Path::new("./tests/data").join("hello.in")));
+
+    let dir = tempfile::tempdir().unwrap();
+    for copy in &relative.test_runner.copy_files {
+        copy.copy_into(&relative, dir.path()).unwrap();
+    }
+    let read = |path: &str| std::fs::read_to_string(dir.path().join(path)).unwrap();
+    assert_eq!("hello\n", read("input.txt"));
+    assert_eq!("alpha\n", read("fixtures/a.txt"));
+    assert_eq!("root\n", read("tree/root.txt"));
+
+    let from_cwd = config(r#"{ from = "./tests/data/hello.in", to = "input.txt" }"#);
+    assert_eq!(
+        Err(vec![validate::ValidationError::MissingCopySource(
+            "./tests/data/hello.in".into()
+        )]),
+        from_cwd.validate()
+    );
+}
+
+#[test]
+fn copy_conflicting_destinations() {
+    let source = tempfile::tempdir().unwrap();
+    for (dir, contents) in [("a", "first"), ("b", "second")] {
+        std::fs::create_dir(source.path().join(dir)).unwrap();
+        std::fs::write(source.path().join(dir).join("x.txt"), contents).unwrap();
+    }
+
+    let config = config_with_copies(vec![
+        FileCopy {
+            from: source.path().join("*/x.txt"),
+            to: "data".into(),
+            ..Default::default()
+        },
+        FileCopy {
+            from: "./tests/data/hello.in".into(),
+            to: "tree".into(),
+            ..Default::default()
+        },
+        FileCopy {
+            from: "./tests/data/tree".into(),
+            to: "./tree".into(),
+            ..Default::default()
+        },
+    ]);
+    let errs = config.validate().unwrap_err();
+    assert!(
+        matches!(
+            &errs[..],
+            [
+                validate::ValidationError::DuplicateCopyDestination { to, .. },
+                validate::ValidationError::CopyDirectoryIntoFile { from, to: file },
+            ] if to == "data/x.txt" && from == "./tests/data/tree" && file == "tree"
+        ),
+        "{errs:?}"
+    );
+}
+
+#[test]
+fn copy_escaping_destination() {
+    let copy = |to: &str| FileCopy {
+        from: "./tests/data/hello.in".into(),
+        to: to.into(),
+        ..Default::default()
+    };
+    let config = Config::builder()
+        .test_runner(TestRunner {
+            copy_files: vec![
+                copy("../input.txt"),
+                copy("/etc/input.txt"),
+                copy("a/../../b"),
+            ],
+            ..Default::default()
+        })
+        .build();
+    assert_eq!(
+        Err(vec![
+            validate::ValidationError::UnsafeCopyDestination("../input.txt".into()),
+            validate::ValidationError::UnsafeCopyDestination("/etc/input.txt".into()),
+            validate::ValidationError::UnsafeCopyDestination("a/../../b".into()),
+        ]),
+        config.validate_copy_files_exist()
+    );
+}
+
+fn ocaml(build: &str) -> Language {
+    Language::Custom {
+        raw_name: "ocaml".into(),
+        name: "ocaml".into(),
+        build: Some(build.into()),
+        run: "./out".into(),
+        source_file: "solution.ml".into(),
+        init: None,
+    }
+}
+
+#[test]
+fn merge_language_sets() {
+    let python = Language::BuiltIn {
+        language: BuiltInLanguage::Python3,
+        version: Version::Latest,
+    };
+    let java = Language::BuiltIn {
+        language: BuiltInLanguage::Java,
+        version: Version::Specific("21".into()),
+    };
+
+    let mut base = LanguageSet::new();
+    base.insert(python.clone());
+    let mut overlay = LanguageSet::new();
+    overlay.insert(java.clone());
+    base.merge(overlay).unwrap();
+    assert_eq!(2, base.len());
+    assert!(base.contains(&java));
+
+    // identical entries are merged silently
+    let mut overlay = LanguageSet::new();
+    overlay.insert(python.clone());
+    overlay.insert(ocaml("ocamlc -o out solution.ml"));
+    base.merge(overlay).unwrap();
+    assert_eq!(3, base.len());
+}
+
+#[test]
+fn merge_language_set_conflicts() {
+    let mut base = LanguageSet::new();
+    base.insert(ocaml("ocamlc -o out solution.ml"));
+    base.insert(Language::BuiltIn {
+        language: BuiltInLanguage::Java,
+        version: Version::Specific("21".into()),
+    });
+
+    let mut overlay = LanguageSet::new();
+    overlay.insert(ocaml("ocamlopt -o out solution.ml"));
+    overlay.insert(Language::BuiltIn {
+        language: BuiltInLanguage::Java,
+        version: Version::Specific("8".into()),
+    });
+    overlay.insert(Language::BuiltIn {
+        language: BuiltInLanguage::Rust,
+        version: Version::Latest,
+    });
+
+    let before = base.clone();
+    let conflicts = base.merge(overlay).unwrap_err();
+    assert_eq!(
+        vec!["java", "ocaml"],
+        conflicts
+            .iter()
+            .map(|c| c.name.as_str())
+            .collect::<Vec<_>>()
+    );
+    assert_eq!(before, base);
+}
+
+#[test]
+fn languages_are_ordered_by_name() {
+    let custom = |raw_name: &str| Language::Custom {
+        raw_name: raw_name.into(),
+        name: raw_name.into(),
+        build: None,
+        run: "./run".into(),
+        source_file: "solution".into(),
+        init: None,
+    };
+    let builtin = |language| Language::BuiltIn {
+        language,
+        version: Version::Latest,
+    };
+
+    let mut languages = vec![
+        builtin(BuiltInLanguage::Rust),
+        custom("python3"),
+        builtin(BuiltInLanguage::Python3),
+        custom("aardvark"),
+        builtin(BuiltInLanguage::Java),
+    ];
+    languages.sort();
+    assert_eq!(
+        vec![
+            custom("aardvark"),
+            builtin(BuiltInLanguage::Java),
+            builtin(BuiltInLanguage::Python3),
+            custom("python3"),
+            builtin(BuiltInLanguage::Rust),
+        ],
+        languages
+    );
+
+    // the same name with different definitions is a conflict rather than the same language
+    assert_ne!(
+        std::cmp::Ordering::Equal,
+        custom("python3").cmp(&builtin(BuiltInLanguage::Python3))
+    );
+    let mut set = LanguageSet::new();
+    set.insert(builtin(BuiltInLanguage::Python3));
+    let mut other = LanguageSet::new();
+    other.insert(custom("python3"));
+    let conflicts = set.merge(other).unwrap_err();
+    assert_eq!("python3", conflicts[0].name);
+}
+
+#[test]
+fn compiled_and_interpreted_languages() {
+    let python = Language::BuiltIn {
+        language: BuiltInLanguage::Python3,
+        version: Version::Latest,
+    };
+    let rust = Language::BuiltIn {
+        language: BuiltInLanguage::Rust,
+        version: Version::Latest,
+    };
+    let mut languages = LanguageSet::new();
+    languages.insert(python.clone());
+    languages.insert(rust.clone());
+
+    assert_eq!(vec![&rust], languages.compiled().collect::<Vec<_>>());
+    assert_eq!(vec![&python], languages.interpreted().collect::<Vec<_>>());
+}
+
+const DESCRIPTION_LIST: &str = "Input :: a number *n*\nOutput :: the value of `n * n`";
+
+#[test]
+fn description_lists_typst() {
+    let world = TypstWrapperWorld::new("");
+    let content = render_markdown(DESCRIPTION_LIST, &world).unwrap();
+    let terms = content
+        .query_first(Selector::Elem(TermsElem::elem(), None))
+        .unwrap();
+    let terms = terms.to_packed::<TermsElem>().unwrap();
+    assert_eq!(2, terms.children.len());
+    assert_eq!("Input", terms.children[0].term.plain_text());
+    assert_eq!("a number n", terms.children[0].description.plain_text());
+    assert_eq!("Output", terms.children[1].term.plain_text());
+}
+
+#[test]
+fn heading_ids_html() {
+    let html = MarkdownRenderable::from("# Foo\n\n## Foo\n\n### Sample `Input`, *too*!\n\n# Foo")
+        .html()
+        .unwrap();
+    assert!(html.contains(r#"<h1 id="foo">Foo</h1>"#), "{}", html);
+    assert!(html.contains(r#"<h2 id="foo-1">Foo</h2>"#), "{}", html);
+    assert!(html.contains(r#"<h1 id="foo-2">Foo</h1>"#), "{}", html);
+    assert!(html.contains(r#"<h3 id="sample-input-too">"#), "{}", html);
+}
+
+#[test]
+fn description_lists_html() {
+    let html = MarkdownRenderable::from(DESCRIPTION_LIST).html().unwrap();
+    assert!(html.contains("<dl>"));
+    assert!(html.contains("<dt>Input</dt>\n<dd>a number <em>n</em></dd>"));
+    assert!(html.contains("<dt>Output</dt>\n<dd>the value of <code>n * n</code></dd>"));
+    assert!(!html.contains("<p>"));
+}
+
+#[test]
+fn description_lists_with_formatting_across_lines() {
+    let markdown = "a :: b *c\nd* :: e";
+    let html = MarkdownRenderable::from(markdown).html().unwrap();
+    assert_eq!(1, html.matches("<dt>").count(), "{}", html);
+    assert_eq!(html.matches("<dd>").count(), html.matches("</dd>").count());
+    assert!(html.contains("<dt>a</dt>"), "{}", html);
+
+    let world = TypstWrapperWorld::new("");
+    let content = render_markdown(markdown, &world).unwrap();
+    let terms = content
+        .query_first(Selector::Elem(TermsElem::elem(), None))
+        .unwrap();
+    let terms = terms.to_packed::<TermsElem>().unwrap();
+    assert_eq!(1, terms.children.len());
+    assert_eq!("a", terms.children[0].term.plain_text());
+
+    // a line break at the start of the emphasis would start a line with an `End` event
+    let html = MarkdownRenderable::from("a :: *b\nc* :: d\ne :: f")
+        .html()
+        .unwrap();
+    assert_eq!(2, html.matches("<dt>").count(), "{}", html);
+}
+
+#[test]
+fn math_limits() {
+    use render::markdown::{MathLimits, RenderError};
+
+    let limits = MathLimits {
+        max_length: 16,
+        max_count: 2,
+    };
+    // this would fail to compile, so getting a length error shows it was never compiled
+    let markdown = format!("Short $x$ and long ${}$", "\\frac".repeat(10));
+    let err = MarkdownRenderable::from(markdown.as_str())
+        .html_with(&limits)
+        .unwrap_err();
+    assert_eq!(
+        RenderError::MathTooLong {
+            span: markdown.find(" $\\").unwrap() + 1..markdown.len(),
+            length: 50,
+            limit: 16,
+        },
+        err
+    );
+
+    let err = MarkdownRenderable::from("$a$ $b$ $c$")
+        .html_with(&limits)
+        .unwrap_err();
+    assert_eq!(
+        RenderError::TooMuchMath {
+            span: 8..11,
+            limit: 2
+        },
+        err
+    );
+    assert!(MarkdownRenderable::from("$a$ $b$")
+        .html_with(&limits)
+        .is_ok());
+
+    let long = format!("${}$", "x".repeat(MathLimits::default().max_length + 1));
+    let errors = MarkdownRenderable::from(long.as_str())
+        .validate()
+        .unwrap_err();
+    assert!(matches!(errors[..], [RenderError::MathTooLong { .. }]));
+}
+
+#[test]
+fn math_errors_have_spans() {
+    let markdown = "Some text before the maths $\\frac$ and after";
+    let formula = markdown.find('$').unwrap()..markdown.rfind('$').unwrap() + 1;
+    let overlaps =
+        |span: std::ops::Range<usize>| span.start < formula.end && formula.start < span.end;
+
+    let world = TypstWrapperWorld::new("");
+    let err = render_markdown(markdown, &world).unwrap_err();
+    assert!(matches!(
+        err,
+        render::markdown::RenderError::MathError { .. }
+    ));
+    assert!(overlaps(err.span().unwrap()));
+
+    let err = MarkdownRenderable::from(markdown).html().unwrap_err();
+    assert!(matches!(
+        err,
+        render::markdown::RenderError::MathError { .. }
+    ));
+    assert!(overlaps(err.span().unwrap()));
+}
@@ -1,5 +1,7 @@
+#[cfg(feature = "render")]
 use serde::{de::DeserializeOwned, Serialize};
 
+#[cfg(feature = "render")]
 pub fn convert<D, S>(value: &S) -> D
 where
     D: DeserializeOwned,
@@ -8,3 +10,83 @@ where
     let value = serde_json::to_value(value).unwrap();
     serde_json::from_value(value).unwrap()
 }
+
+/// How a hash is encoded as a string, see [`Config::hash_with`](crate::Config::hash_with)
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct HashOptions {
+    alphabet: Vec<char>,
+    min_length: usize,
+}
+
+impl HashOptions {
+    /// The alphabet used by [`Config::hash`](crate::Config::hash)
+    pub const BASE36: &'static str = "abcdefghijklmnopqrstuvwxyz0123456789";
+    /// An alphabet which is safe to use in URLs, including upper and lower case letters
+    pub const URL_SAFE: &'static str =
+        "abcdefghijklmnopqrstuvwxyzABCDEFGHIJKLMNOPQRSTUVWXYZ0123456789-_";
+
+    /// Encode using the characters of `alphabet`, where the first character is used as zero
+    ///
+    /// Returns `None` if `alphabet` has fewer than two characters or contains duplicates.
+    pub fn with_alphabet(mut self, alphabet: &str) -> Option<Self> {
+        let chars = alphabet.chars().collect::<Vec<_>>();
+        let unique = chars.iter().collect::<std::collections::HashSet<_>>();
+        if chars.len() < 2 || unique.len() != chars.len() {
+            return None;
+        }
+        self.alphabet = chars;
+        Some(self)
+    }
+
+    /// Pad the hash with zeros (the first character of the alphabet) to at least `min_length`
+    pub fn with_min_length(mut self, min_length: usize) -> Self {
+        self.min_length = min_length;
+        self
+    }
+}
+
+impl Default for HashOptions {
+    fn default() -> Self {
+        Self {
+            alphabet: Self::BASE36.chars().collect(),
+            min_length: 0,
+        }
+    }
+}
+
+/// Encode a hash in base 36
+pub fn encode_hash(hash: u64) -> String {
+    encode_hash_with(hash, &HashOptions::default())
+}
+
+/// Encode a hash using the alphabet and length from `options`
+///
+/// Digits are written least significant first.
+pub fn encode_hash_with(mut hash: u64, options: &HashOptions) -> String {
+    let n = options.alphabet.len() as u64;
+    let mut out = String::with_capacity(options.min_length.max(14));
+    let mut len = 0;
+    loop {
+        out.push(options.alphabet[(hash % n) as usize]);
+        len += 1;
+        hash /= n;
+        if hash == 0 {
+            break;
+        }
+    }
+    for _ in len..options.min_length {
+        out.push(options.alphabet[0]);
+    }
+    out
+}
+
+/// Convert a 1-based line and column into a byte offset into `content`
+#[cfg(feature = "json")]
+pub fn offset_of(content: &str, line: usize, column: usize) -> usize {
+    let line_start = content
+        .split_inclusive('\n')
+        .take(line.saturating_sub(1))
+        .map(str::len)
+        .sum::<usize>();
+    (line_start + column.saturating_sub(1)).min(content.len())
+}
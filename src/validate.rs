@@ -0,0 +1,395 @@
+use std::path::{Component, Path, PathBuf};
+
+use miette::Diagnostic;
+
+use crate::{language::Language, packet::Problem, Config, FileCopy, FileCopyError};
+
+/// A problem with a config that is not detected while parsing
+#[derive(Debug, thiserror::Error, Diagnostic, PartialEq, Eq)]
+pub enum ValidationError {
+    /// There are no languages, so competitors could not submit solutions in any language
+    #[error("No languages are configured, so competitors cannot submit solutions")]
+    NoLanguages,
+    /// The competition ends before it starts
+    #[error("The end_time ({end}) is before the start_time ({start})")]
+    EndBeforeStart {
+        start: time::OffsetDateTime,
+        end: time::OffsetDateTime,
+    },
+    /// A custom language has an empty `run` or `source_file`
+    #[error("Custom language '{language}' has an empty `{field}`")]
+    EmptyLanguageField {
+        language: String,
+        field: &'static str,
+    },
+    /// The test runner has an override for a language which is not in the config
+    #[error("Test runner override refers to unknown language '{0}'")]
+    UnknownOverrideLanguage(String),
+    /// A file to copy is an invalid glob pattern
+    #[error("Invalid glob pattern in copy_files '{pattern}': {message}")]
+    InvalidCopyPattern { pattern: String, message: String },
+    /// A file to copy is a glob pattern which does not match any files
+    #[error("Pattern in copy_files '{0}' did not match any files")]
+    NoCopyMatches(String),
+    /// A file to copy does not exist
+    #[error("File in copy_files '{0}' does not exist")]
+    MissingCopySource(String),
+    /// The destination of a file to copy is absolute or escapes the test directory
+    #[error("Destination in copy_files '{0}' must be a relative path within the test directory")]
+    UnsafeCopyDestination(String),
+    /// A glob pattern in copy_files matches more than one file with the same name
+    #[error("Files in copy_files '{first}' and '{second}' would both be copied to '{to}'")]
+    DuplicateCopyDestination {
+        first: String,
+        second: String,
+        to: String,
+    },
+    /// A directory (or the files matching a glob pattern) would be copied to where a file is
+    /// copied
+    #[error("Directory in copy_files '{from}' cannot be copied into '{to}', which is a file")]
+    CopyDirectoryIntoFile { from: String, to: String },
+    /// A problem allows a language which is not in the config
+    #[error("Problem '{problem}' allows unknown language '{language}'")]
+    UnknownProblemLanguage { problem: String, language: String },
+    /// A problem has a checker, but does not say which language it is written in
+    #[error("Problem '{0}' has a checker but no checker_language")]
+    MissingCheckerLanguage(String),
+    /// A problem's checker is written in a language which is not in the config
+    #[error("Checker for problem '{problem}' uses unknown language '{language}'")]
+    UnknownCheckerLanguage { problem: String, language: String },
+}
+
+impl Config {
+    /// Check the config for inconsistencies between sections
+    ///
+    /// All errors are reported rather than just the first.
+    pub fn validate(&self) -> Result<(), Vec<ValidationError>> {
+        let mut errs = Vec::new();
+
+        if self.languages.is_empty() {
+            errs.push(ValidationError::NoLanguages);
+        }
+
+        if let Some((start, end)) = self.start_time.zip(self.end_time) {
+            if end < start {
+                errs.push(ValidationError::EndBeforeStart { start, end });
+            }
+        }
+
+        let mut custom = self
+            .languages
+            .iter()
+            .filter_map(|language| match language {
+                Language::Custom {
+                    raw_name,
+                    run,
+                    source_file,
+                    ..
+                } => Some((raw_name, run, source_file)),
+                Language::BuiltIn { .. } => None,
+            })
+            .collect::<Vec<_>>();
+        custom.sort();
+        for (name, run, source_file) in custom {
+            for (field, value) in [("run", run), ("source_file", source_file)] {
+                if value.trim().is_empty() {
+                    errs.push(ValidationError::EmptyLanguageField {
+                        language: name.clone(),
+                        field,
+                    });
+                }
+            }
+        }
+
+        for name in self.test_runner.language_overrides.keys() {
+            if self.languages.get_by_str(name).is_none() {
+                errs.push(ValidationError::UnknownOverrideLanguage(name.clone()));
+            }
+        }
+
+        if let Err(copy_errs) = self.validate_copy_files_exist() {
+            errs.extend(copy_errs);
+        }
+
+        for problem in self.packet.iter_problems() {
+            for language in problem.languages.iter().flatten() {
+                if self.languages.get_by_str(language).is_none() {
+                    errs.push(ValidationError::UnknownProblemLanguage {
+                        problem: problem.title.clone(),
+                        language: language.clone(),
+                    });
+                }
+            }
+
+            if problem.checker.is_none() {
+                continue;
+            }
+            match &problem.checker_language {
+                None => errs.push(ValidationError::MissingCheckerLanguage(
+                    problem.title.clone(),
+                )),
+                Some(language) if self.languages.get_by_str(language).is_none() => {
+                    errs.push(ValidationError::UnknownCheckerLanguage {
+                        problem: problem.title.clone(),
+                        language: language.clone(),
+                    })
+                }
+                Some(_) => {}
+            }
+        }
+
+        if errs.is_empty() {
+            Ok(())
+        } else {
+            Err(errs)
+        }
+    }
+
+    /// Check that every file in [`TestRunner::copy_files`](crate::TestRunner::copy_files) can
+    /// be copied
+    ///
+    /// Each source must exist (relative to the directory containing the config, see
+    /// [`Config::resolve_path`]) and each destination must be a relative path which stays within
+    /// the test directory.  No two files may be copied to the same place, and no directory may be
+    /// copied to where a file is copied.
+    pub fn validate_copy_files_exist(&self) -> Result<(), Vec<ValidationError>> {
+        let mut errs = Vec::new();
+        // where files and directories are copied to, along with what is copied there
+        let (mut files, mut dirs) = (Vec::new(), Vec::new());
+
+        for copy in &self.test_runner.copy_files {
+            match copy.sources(self) {
+                Ok(sources) if copy.is_glob() => {
+                    dirs.push((normalize(&copy.to), copy));
+                    match copy.glob_destinations(sources) {
+                        Ok(dests) => {
+                            files.extend(dests.into_keys().map(|to| (normalize(&to), copy)))
+                        }
+                        Err(err) => errs.extend(copy_error(copy, err)),
+                    }
+                }
+                Ok(_) if copy.is_dir(self) => dirs.push((normalize(&copy.to), copy)),
+                Ok(sources) if sources.iter().all(|s| s.exists()) => {
+                    files.push((normalize(&copy.to), copy))
+                }
+                Ok(_) => errs.push(ValidationError::MissingCopySource(
+                    copy.from.display().to_string(),
+                )),
+                Err(err) => errs.extend(copy_error(copy, err)),
+            }
+
+            if let Err(err) = copy.exclude_patterns() {
+                errs.extend(copy_error(copy, err));
+            }
+
+            if escapes_directory(&copy.to) {
+                errs.push(ValidationError::UnsafeCopyDestination(
+                    copy.to.display().to_string(),
+                ));
+            }
+        }
+
+        for (dir, copy) in dirs {
+            if let Some((file, _)) = files.iter().find(|(file, _)| dir.starts_with(file)) {
+                errs.push(ValidationError::CopyDirectoryIntoFile {
+                    from: copy.from.display().to_string(),
+                    to: file.display().to_string(),
+                });
+            }
+        }
+
+        if errs.is_empty() {
+            Ok(())
+        } else {
+            Err(errs)
+        }
+    }
+}
+
+/// The [`ValidationError`] for a [`FileCopyError`] from `copy`
+///
+/// IO errors and directories which are too large are only found while copying, so they are not
+/// reported.
+fn copy_error(copy: &FileCopy, err: FileCopyError) -> Option<ValidationError> {
+    let display = |path: &Path| path.display().to_string();
+    Some(match err {
+        FileCopyError::Pattern { pattern, source } => ValidationError::InvalidCopyPattern {
+            pattern,
+            message: source.to_string(),
+        },
+        FileCopyError::NoMatches(_) => ValidationError::NoCopyMatches(display(&copy.from)),
+        FileCopyError::DirectoryIntoFile { to, .. } => ValidationError::CopyDirectoryIntoFile {
+            from: display(&copy.from),
+            to: display(&to),
+        },
+        FileCopyError::DuplicateDestination { first, second, to } => {
+            ValidationError::DuplicateCopyDestination {
+                first: display(&first),
+                second: display(&second),
+                to: display(&to),
+            }
+        }
+        FileCopyError::UnsafeDestination(to) => {
+            ValidationError::UnsafeCopyDestination(display(&to))
+        }
+        FileCopyError::DirectoryTooLarge(_) | FileCopyError::Io(_) => return None,
+    })
+}
+
+/// `path` without any `.` components, so that equivalent destinations compare equal
+fn normalize(path: &Path) -> PathBuf {
+    path.components()
+        .filter(|c| !matches!(c, Component::CurDir))
+        .collect()
+}
+
+/// Whether joining `path` onto a directory could refer to something outside of that directory
+pub(crate) fn escapes_directory(path: &Path) -> bool {
+    let mut depth = 0usize;
+    for component in path.components() {
+        match component {
+            Component::Prefix(_) | Component::RootDir => return true,
+            Component::CurDir => {}
+            Component::ParentDir => match depth.checked_sub(1) {
+                Some(d) => depth = d,
+                None => return true,
+            },
+            Component::Normal(_) => depth += 1,
+        }
+    }
+    false
+}
+
+impl Problem {
+    /// Find things in this problem that are valid, but look wrong
+    ///
+    /// This is included in [`Config::lint`].
+    pub fn lint(&self) -> Vec<Lint> {
+        let mut lints = Vec::new();
+
+        if self.tests.is_empty() {
+            lints.push(Lint::new(
+                Severity::Warning,
+                format!("Problem '{}' has no tests", self.title),
+            ));
+        } else if self.example_test().is_none() {
+            lints.push(Lint::new(
+                Severity::Warning,
+                format!(
+                    "Problem '{}' has no visible tests, so competitors will not see an example",
+                    self.title
+                ),
+            ));
+        }
+
+        if self
+            .description
+            .as_ref()
+            .is_some_and(|d| d.raw().trim().is_empty())
+        {
+            lints.push(Lint::new(
+                Severity::Info,
+                format!("Problem '{}' has an empty description", self.title),
+            ));
+        }
+
+        lints
+    }
+}
+
+/// How serious a [`Lint`] is
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Ord, PartialOrd, Hash)]
+pub enum Severity {
+    /// Probably intentional, but worth knowing about
+    Info,
+    /// Probably a mistake
+    Warning,
+}
+
+/// A non-fatal issue with a config which should be checked before the competition goes live
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Lint {
+    pub severity: Severity,
+    pub message: String,
+}
+
+impl Lint {
+    fn new(severity: Severity, message: impl Into<String>) -> Self {
+        Self {
+            severity,
+            message: message.into(),
+        }
+    }
+}
+
+impl std::fmt::Display for Lint {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self.severity {
+            Severity::Info => write!(f, "info: {}", self.message),
+            Severity::Warning => write!(f, "warning: {}", self.message),
+        }
+    }
+}
+
+impl Config {
+    /// Find things in the config that are valid, but look wrong
+    pub fn lint(&self) -> Vec<Lint> {
+        let mut lints = Vec::new();
+
+        if self
+            .packet
+            .preamble
+            .as_ref()
+            .is_some_and(|p| p.raw().trim().is_empty())
+        {
+            lints.push(Lint::new(Severity::Info, "The packet preamble is empty"));
+        }
+
+        let mut languages = self.languages.iter().collect::<Vec<_>>();
+        languages.sort_by_key(|l| l.raw_name());
+
+        for problem in self.packet.iter_problems() {
+            lints.extend(problem.lint());
+
+            // competitors who only know one of these cannot submit anything for this problem
+            let Some(allowed) = &problem.languages else {
+                continue;
+            };
+            let effective = self.effective_languages(problem);
+            let excluded = languages
+                .iter()
+                .filter(|l| !effective.contains(l))
+                .map(|l| l.raw_name())
+                .collect::<Vec<_>>();
+            if !excluded.is_empty() {
+                lints.push(Lint::new(
+                    Severity::Info,
+                    format!(
+                        "Problem '{}' only allows {}, so it cannot be solved in {}",
+                        problem.title,
+                        allowed.iter().cloned().collect::<Vec<_>>().join(", "),
+                        excluded.join(", ")
+                    ),
+                ));
+            }
+        }
+
+        for language in languages {
+            let used = self
+                .packet
+                .iter_problems()
+                .any(|p| self.effective_languages(p).contains(&language));
+            if !used {
+                lints.push(Lint::new(
+                    Severity::Info,
+                    format!(
+                        "Language '{}' is not allowed by any problem",
+                        language.raw_name()
+                    ),
+                ));
+            }
+        }
+
+        lints
+    }
+}
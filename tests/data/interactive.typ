@@ -0,0 +1,3 @@
+= #problem.title (interactive)
+
+CUSTOM-TEMPLATE-MARKER
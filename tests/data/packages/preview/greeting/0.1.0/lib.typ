@@ -0,0 +1 @@
+#let greet(name) = [Hello, #name!]
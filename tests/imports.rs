@@ -1,4 +1,7 @@
-use bedrock::Config;
+use std::path::PathBuf;
+
+use bedrock::{render::typst::TypstWrapperWorld, roi::Origin, Config};
+use miette::Diagnostic;
 
 const FILE: &str = include_str!("./imports.toml");
 const SETUP_FILE: &str = include_str!("./setup.toml");
@@ -18,3 +21,267 @@ fn parse_get() -> miette::Result<()> {
     assert_eq!(setup, setup_toml);
     Ok(())
 }
+
+#[test]
+fn origins() -> miette::Result<()> {
+    let config = Config::from_str(FILE, Some("imports.toml"))?;
+    assert_eq!(
+        Origin::Imported(PathBuf::from("./tests/setup.toml")),
+        config.setup.as_ref().unwrap().origin()
+    );
+    assert_eq!(Origin::Inline, config.languages.origin());
+    Ok(())
+}
+
+#[test]
+fn dependency_paths() -> miette::Result<()> {
+    let config = Config::from_str(FILE, Some("imports.toml"))?;
+    assert_eq!(
+        vec![
+            PathBuf::from("./tests/packet.toml"),
+            PathBuf::from("./tests/setup.toml")
+        ],
+        config.dependency_paths()
+    );
+    Ok(())
+}
+
+fn config_with_setup_hash(sha256: Option<&str>) -> Result<Config, bedrock::ConfigReadError> {
+    let sha256 = sha256
+        .map(|h| format!("sha256 = \"{}\"", h))
+        .unwrap_or_default();
+    let content = format!(
+        r#"
+[setup]
+import = "./tests/setup.toml"
+{}
+
+[languages]
+python3 = "latest"
+
+[accounts]
+admins = []
+competitors = []
+
+[packet]
+title = "Checksums"
+problems = []
+"#,
+        sha256
+    );
+    Config::from_str(content, Some("checksums.toml"))
+}
+
+#[test]
+fn import_matching_checksum() -> miette::Result<()> {
+    let config = config_with_setup_hash(Some(
+        "49246ab0bcf09bb7ad7c35af474c416b0bf5e378e00a1fd28edde3da9e5ea943",
+    ))?;
+    assert!(config.setup.is_some());
+    Ok(())
+}
+
+#[test]
+fn import_mismatching_checksum() {
+    let err = config_with_setup_hash(Some(
+        "0000000000000000000000000000000000000000000000000000000000000000",
+    ))
+    .unwrap_err();
+    assert!(format!("{:?}", err).contains("Checksum mismatch for ./tests/setup.toml"));
+}
+
+#[test]
+fn import_without_checksum() -> miette::Result<()> {
+    let config = config_with_setup_hash(None)?;
+    assert!(config.setup.is_some());
+    Ok(())
+}
+
+fn config_with_preamble(path: &str) -> miette::Result<Config> {
+    let content = format!(
+        r#"
+[languages]
+python3 = "latest"
+
+[accounts]
+admins = []
+competitors = []
+
+[packet]
+title = "Markdown imports"
+preamble = {{ import = "{}" }}
+problems = []
+"#,
+        path
+    );
+    Ok(Config::from_str(content, Some("preamble.toml"))?)
+}
+
+#[test]
+fn import_markdown_preamble() -> miette::Result<()> {
+    let config = config_with_preamble("./tests/data/preamble.md")?;
+    let preamble = config.packet.preamble.as_ref().unwrap();
+    assert_eq!(
+        std::fs::read_to_string("./tests/data/preamble.md").unwrap(),
+        preamble.raw()
+    );
+
+    let world = TypstWrapperWorld::new("");
+    let content = preamble.content(&world).unwrap();
+    let text = content.plain_text();
+    assert!(text.contains("Welcome to the competition"));
+    assert!(text.contains("Submitting"));
+    assert!(text.contains("print(input()[::-1])"));
+
+    let html = preamble.html().unwrap();
+    assert!(html.contains(r#"<h1 id="welcome-to-the-competition">Welcome to the competition</h1>"#));
+    Ok(())
+}
+
+#[test]
+fn import_markdown_with_byte_order_mark() -> miette::Result<()> {
+    let config = config_with_preamble("./tests/data/preamble-bom.md")?;
+    let preamble = config.packet.preamble.as_ref().unwrap();
+    assert!(preamble.raw().starts_with("# Welcome"));
+    assert!(preamble
+        .html()
+        .unwrap()
+        .starts_with(r#"<h1 id="welcome-to-the-competition">Welcome to the competition</h1>"#));
+    Ok(())
+}
+
+#[test]
+fn import_reference_solution() -> miette::Result<()> {
+    let config = Config::from_str(
+        r#"
+[languages]
+python3 = "latest"
+
+[accounts]
+admins = []
+competitors = []
+
+[packet]
+title = "Solutions"
+
+[[packet.problems]]
+title = "Square"
+solution = { import = "./tests/data/solution.py" }
+solution_language = "python3"
+tests = [{ input = "3", output = "9", visible = true }]
+"#,
+        Some("solution.toml"),
+    )?;
+    let problem = &config.packet.problems[0];
+    assert_eq!(
+        std::fs::read_to_string("./tests/data/solution.py").unwrap(),
+        **problem.solution.as_ref().unwrap()
+    );
+    assert_eq!(Some("python3"), problem.solution_language.as_deref());
+    assert!(config
+        .dependency_paths()
+        .contains(&PathBuf::from("./tests/data/solution.py")));
+
+    let view = config.competitor_view();
+    let problem = &view.packet.problems[0];
+    assert_eq!(None, problem.solution);
+    assert_eq!(None, problem.solution_language);
+    Ok(())
+}
+
+#[test]
+fn import_list_of_problems() -> miette::Result<()> {
+    let config = Config::from_str(
+        r#"
+[languages]
+python3 = "latest"
+
+[accounts]
+admins = []
+competitors = []
+
+[packet]
+title = "Arithmetic"
+problems = [
+    { import = "./tests/data/problem.toml" },
+    { import = "./tests/data/problems.toml" },
+]
+"#,
+        Some("problems.toml"),
+    )?;
+    assert_eq!(4, config.packet.problem_count());
+    let titles = config
+        .packet
+        .iter_problems()
+        .map(|p| p.title.as_str())
+        .collect::<Vec<_>>();
+    assert_eq!(
+        vec!["Imported problem", "Add", "Subtract", "Multiply"],
+        titles
+    );
+    assert!(config
+        .dependency_paths()
+        .contains(&PathBuf::from("./tests/data/problems.toml")));
+    Ok(())
+}
+
+#[test]
+fn malformed_import_points_into_imported_file() {
+    let err = Config::from_str(
+        r#"
+[languages]
+python3 = "latest"
+
+[accounts]
+admins = []
+competitors = []
+
+[packet]
+title = "Malformed"
+problems = [{ import = "./tests/data/malformed.toml" }]
+"#,
+        Some("malformed.toml"),
+    )
+    .unwrap_err();
+    let source = err.source_code().unwrap();
+    let label = err.labels().unwrap().next().unwrap();
+    let contents = source.read_span(label.inner(), 0, 0).unwrap();
+    assert_eq!(Some("./tests/data/malformed.toml"), contents.name());
+    assert_eq!(
+        "\"very\"",
+        std::str::from_utf8(contents.data()).unwrap().trim()
+    );
+}
+
+#[test]
+fn import_errors_are_not_reported_for_later_failures() {
+    // deserialising without `Config` leaves the import error behind
+    let packet = toml_edit::de::from_str::<bedrock::packet::Packet>(
+        r#"
+title = "Malformed"
+problems = [{ import = "./tests/data/malformed.toml" }]
+"#,
+    );
+    assert!(packet.is_err());
+
+    let err = Config::from_str(
+        r#"
+[languages]
+python3 = "latest"
+
+[accounts]
+admins = []
+competitors = []
+
+[packet]
+title = 7
+problems = []
+"#,
+        Some("unrelated.toml"),
+    )
+    .unwrap_err();
+    let source = err.source_code().unwrap();
+    let label = err.labels().unwrap().next().unwrap();
+    let contents = source.read_span(label.inner(), 0, 0).unwrap();
+    assert_eq!(Some("unrelated.toml"), contents.name());
+}